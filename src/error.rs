@@ -1,4 +1,6 @@
+use std::fmt;
 use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -9,6 +11,10 @@ pub enum GrokError {
     #[error("Configuration invalid: {0}")]
     ConfigInvalid(String),
 
+    /// A tool argument that passed validation but cannot be used, e.g. a URL that does not parse.
+    #[error("Invalid parameter: {0}")]
+    InvalidParams(String),
+
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
 
@@ -18,20 +24,218 @@ pub enum GrokError {
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("API error ({status}): {message}")]
-    Api { status: u16, message: String },
+    #[error("API error ({status}): {error}")]
+    Api { status: u16, error: ApiError },
+
+    /// A `response.failed` or `error` event inside a stream that had started with 200 OK.
+    /// It describes the request itself (model, validation), so it is neither retried nor an outage.
+    #[error("API reported an error in the stream: {0}")]
+    StreamError(ApiError),
+
+    #[error("Rate limited by API{}", retry_after_suffix(.retry_after))]
+    RateLimited { status: u16, retry_after: Option<Duration> },
 
     #[error("Timeout after {0} seconds")]
     Timeout(u64),
 
-    #[error("Max retries exceeded ({attempts} attempts): {last_error}")]
-    MaxRetries { attempts: u32, last_error: String },
+    /// `attempts_detail` holds each attempt's number and `[CODE] message (N ms)`, in order.
+    #[error("Max retries exceeded ({attempts} attempts in {} ms): {last_error}", .elapsed.as_millis())]
+    MaxRetries {
+        attempts: u32,
+        last_error: String,
+        last_code: &'static str,
+        attempts_detail: Vec<(u32, String)>,
+        elapsed: Duration,
+    },
 
-    #[error("Invalid parameter: {0}")]
-    InvalidParam(String),
+    /// A non-retryable error that ended a retried request early, e.g. a 401 after a 500.
+    /// It reads and classifies as `error` itself; `attempts_detail` is as for `MaxRetries`.
+    #[error("{error}")]
+    AfterRetries {
+        error: Box<GrokError>,
+        attempts_detail: Vec<(u32, String)>,
+        elapsed: Duration,
+    },
 
     #[error("Config file error at {path}: {message}")]
     ConfigFile { path: PathBuf, message: String },
+
+    #[error("robots.txt at {robots_url} disallows fetching {url}")]
+    RobotsDisallowed { url: String, robots_url: String },
+
+    #[error("Endpoint returned {content_type} instead of JSON; check authentication and proxy settings. Body starts with: {preview}")]
+    NotJson { content_type: String, preview: String },
+
+    #[error("Fetched content from {url} is only {chars} characters (minimum {min_chars}); the page likely requires JavaScript rendering")]
+    ContentTooShort { url: String, chars: usize, min_chars: usize },
+
+    #[error("Request cancelled by the client")]
+    Cancelled,
+
+    #[error("Unknown or expired continue_token '{0}'; fetch the page again for a new one")]
+    ContinueTokenInvalid(String),
+
+    #[error("Circuit breaker open after repeated API failures; retry in {} seconds", .retry_in.as_secs().max(1))]
+    CircuitOpen { retry_in: Duration },
+}
+
+impl GrokError {
+    /// Stable machine-readable error class, e.g. `TIMEOUT`, `RATE_LIMIT` or `AUTH_ERROR`.
+    /// `MaxRetries` reports the class of the last attempt's error, `AfterRetries` that of the wrapped one.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::ConfigMissing(_) => "CONFIG_MISSING",
+            Self::ConfigInvalid(_) => "CONFIG_INVALID",
+            Self::InvalidParams(_) => "INVALID_PARAMS",
+            Self::ConfigFile { .. } => "CONFIG_FILE_ERROR",
+            Self::Http(e) => http_error_code(e),
+            Self::JsonParse(_) => "PARSE_ERROR",
+            Self::Io(_) => "IO_ERROR",
+            Self::Api { status, .. } => classify_status(*status),
+            Self::StreamError(_) => "STREAM_ERROR",
+            Self::RateLimited { .. } => "RATE_LIMIT",
+            Self::Timeout(_) => "TIMEOUT",
+            Self::MaxRetries { last_code, .. } => last_code,
+            Self::AfterRetries { error, .. } => error.code(),
+            Self::CircuitOpen { .. } => "CIRCUIT_OPEN",
+            Self::RobotsDisallowed { .. } => "ROBOTS_DISALLOWED",
+            Self::ContentTooShort { .. } => "CONTENT_TOO_SHORT",
+            Self::NotJson { .. } => "UNEXPECTED_RESPONSE",
+            Self::Cancelled => "CANCELLED",
+            Self::ContinueTokenInvalid(_) => "INVALID_CONTINUE_TOKEN",
+        }
+    }
+
+    /// Stable snake_case name of the variant, used as a metrics label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::ConfigMissing(_) => "config_missing",
+            Self::ConfigInvalid(_) => "config_invalid",
+            Self::InvalidParams(_) => "invalid_params",
+            Self::Http(_) => "http",
+            Self::JsonParse(_) => "json_parse",
+            Self::Io(_) => "io",
+            Self::Api { .. } => "api",
+            Self::StreamError(_) => "stream_error",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::Timeout(_) => "timeout",
+            Self::MaxRetries { .. } => "max_retries",
+            Self::AfterRetries { error, .. } => error.kind(),
+            Self::ConfigFile { .. } => "config_file",
+            Self::CircuitOpen { .. } => "circuit_open",
+            Self::RobotsDisallowed { .. } => "robots_disallowed",
+            Self::ContentTooShort { .. } => "content_too_short",
+            Self::NotJson { .. } => "not_json",
+            Self::Cancelled => "cancelled",
+            Self::ContinueTokenInvalid(_) => "continue_token_invalid",
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, GrokError>;
+
+/// Classifies a transport failure. TLS failures also count as connect errors in reqwest,
+/// so they are told apart by the error's source chain.
+pub fn http_error_code(e: &reqwest::Error) -> &'static str {
+    if e.is_timeout() {
+        "TIMEOUT"
+    } else if is_tls_error(e) {
+        "TLS_ERROR"
+    } else if e.is_connect() {
+        "CONNECTION_FAILURE"
+    } else {
+        "NETWORK_ERROR"
+    }
+}
+
+/// Whether the TLS handshake failed, e.g. an untrusted certificate from an intercepting proxy.
+/// The connector wraps the rustls error in (nested) `io::Error`s, whose `source()` skips the
+/// wrapped error itself, so those are unwrapped with `get_ref` instead.
+pub fn is_tls_error(e: &reqwest::Error) -> bool {
+    let mut source: Option<&dyn std::error::Error> = Some(e);
+    while let Some(err) = source {
+        if err.is::<rustls::Error>() {
+            return true;
+        }
+        source = match err.downcast_ref::<std::io::Error>() {
+            Some(io) => io.get_ref().map(|inner| inner as &dyn std::error::Error),
+            None => err.source(),
+        };
+    }
+    false
+}
+
+/// The error and all of its causes, e.g. `error sending request: ...: invalid peer certificate: UnknownIssuer`.
+pub fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut out = e.to_string();
+    let mut source = e.source();
+    while let Some(err) = source {
+        let text = err.to_string();
+        if !out.contains(&text) {
+            out.push_str(": ");
+            out.push_str(&text);
+        }
+        source = err.source();
+    }
+    out
+}
+
+/// Maps an HTTP status from the API to an error class.
+pub fn classify_status(code: u16) -> &'static str {
+    match code {
+        401 | 403 => "AUTH_ERROR",
+        404 => "NOT_FOUND",
+        429 => "RATE_LIMIT",
+        500..=599 => "SERVER_ERROR",
+        _ => "HTTP_ERROR",
+    }
+}
+
+/// Error details returned by the API, e.g. `{"error": {"message": "...", "code": "..."}}`.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { code: None, message: message.into() }
+    }
+
+    /// Extracts the message and code from a JSON error body, falling back to the raw text.
+    pub fn from_body(body: &str) -> Self {
+        let Ok(v) = serde_json::from_str::<serde_json::Value>(body) else {
+            return Self::new(body.trim());
+        };
+        let err = v.get("error").unwrap_or(&v);
+        let message = err.as_str()
+            .or_else(|| err.get("message").and_then(|m| m.as_str()))
+            .or_else(|| v.get("message").and_then(|m| m.as_str()));
+        let code = err.get("code").or_else(|| err.get("type")).and_then(|c| match c {
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        });
+        match message {
+            Some(message) => Self { code, message: message.to_string() },
+            None => Self::new(body.trim()),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{} [{}]", self.message, code),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+fn retry_after_suffix(retry_after: &Option<Duration>) -> String {
+    match retry_after {
+        Some(d) => format!(" (retry after {}s)", d.as_secs()),
+        None => String::new(),
+    }
+}
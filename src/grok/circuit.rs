@@ -0,0 +1,160 @@
+use crate::error::{GrokError, Result};
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    /// One trial request is in flight after the cooldown.
+    HalfOpen { since: Instant },
+}
+
+/// Snapshot of the breaker for `health_check` and `get_config_info`.
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitStatus {
+    pub state: &'static str,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_secs: Option<u64>,
+}
+
+/// Fails fast once `threshold` consecutive outage errors have been seen, then lets a single
+/// trial request through after `cooldown`. State is shared across clones.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Arc<Mutex<State>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self { threshold, cooldown, state: Arc::new(Mutex::new(State::Closed { failures: 0 })) }
+    }
+
+    /// Returns `CircuitOpen` while the breaker is open, or while another trial request is in flight.
+    pub fn check(&self) -> Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match *state {
+            State::Closed { .. } => Ok(()),
+            State::Open { until } if now < until => Err(GrokError::CircuitOpen { retry_in: until - now }),
+            // A trial that never reported back (e.g. a cancelled call) must not wedge the breaker.
+            State::HalfOpen { since } if now < since + self.cooldown => {
+                Err(GrokError::CircuitOpen { retry_in: since + self.cooldown - now })
+            }
+            State::Open { .. } | State::HalfOpen { .. } => {
+                *state = State::HalfOpen { since: now };
+                Ok(())
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = State::Closed { failures: 0 };
+    }
+
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let failures = match *state {
+            State::Closed { failures } => failures + 1,
+            State::Open { .. } | State::HalfOpen { .. } => self.threshold,
+        };
+        *state = if failures >= self.threshold {
+            tracing::warn!("Circuit breaker opened after {} consecutive failures", failures);
+            State::Open { until: Instant::now() + self.cooldown }
+        } else {
+            State::Closed { failures }
+        };
+    }
+
+    pub fn status(&self) -> CircuitStatus {
+        let state = *self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match state {
+            State::Closed { failures } => CircuitStatus { state: "closed", consecutive_failures: failures, retry_in_secs: None },
+            State::Open { until } => CircuitStatus {
+                state: "open",
+                consecutive_failures: self.threshold,
+                retry_in_secs: Some(until.saturating_duration_since(Instant::now()).as_secs()),
+            },
+            State::HalfOpen { .. } => CircuitStatus { state: "half_open", consecutive_failures: self.threshold, retry_in_secs: None },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOLDOWN: Duration = Duration::from_millis(50);
+
+    fn opened() -> CircuitBreaker {
+        let breaker = CircuitBreaker::new(3, COOLDOWN);
+        for _ in 0..3 {
+            breaker.record_failure();
+        }
+        breaker
+    }
+
+    #[test]
+    fn opens_after_threshold_consecutive_failures() {
+        let breaker = CircuitBreaker::new(3, COOLDOWN);
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(breaker.status().state, "closed");
+        assert_eq!(breaker.status().consecutive_failures, 2);
+        assert!(breaker.check().is_ok());
+        breaker.record_failure();
+        assert_eq!(breaker.status().state, "open");
+        assert!(matches!(breaker.check(), Err(GrokError::CircuitOpen { .. })));
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let breaker = CircuitBreaker::new(3, COOLDOWN);
+        breaker.record_failure();
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.status().state, "closed");
+        assert_eq!(breaker.status().consecutive_failures, 1);
+    }
+
+    #[test]
+    fn lets_one_trial_through_after_cooldown() {
+        let breaker = opened();
+        std::thread::sleep(COOLDOWN);
+        assert!(breaker.check().is_ok());
+        assert_eq!(breaker.status().state, "half_open");
+        // Only one trial at a time.
+        assert!(matches!(breaker.check(), Err(GrokError::CircuitOpen { .. })));
+    }
+
+    #[test]
+    fn trial_outcome_closes_or_reopens() {
+        let breaker = opened();
+        std::thread::sleep(COOLDOWN);
+        breaker.check().unwrap();
+        breaker.record_success();
+        assert_eq!(breaker.status().state, "closed");
+        assert_eq!(breaker.status().consecutive_failures, 0);
+
+        let breaker = opened();
+        std::thread::sleep(COOLDOWN);
+        breaker.check().unwrap();
+        breaker.record_failure();
+        assert_eq!(breaker.status().state, "open");
+        assert!(breaker.check().is_err());
+    }
+
+    #[test]
+    fn stale_trial_does_not_wedge_the_breaker() {
+        let breaker = opened();
+        std::thread::sleep(COOLDOWN);
+        breaker.check().unwrap();
+        std::thread::sleep(COOLDOWN);
+        assert!(breaker.check().is_ok());
+    }
+}
@@ -1,17 +1,41 @@
-use super::prompts::{FETCH_PROMPT, SEARCH_PROMPT};
-use crate::config::Config;
-use crate::error::{GrokError, Result};
-use chrono::Local;
+use super::circuit::{CircuitBreaker, CircuitStatus};
+use super::metrics::Metrics;
+use super::prompts::{FETCH_HTML_PROMPT, FETCH_PROMPT, FETCH_SUMMARY_PROMPT, FETCH_TEXT_PROMPT, SEARCH_PROMPT};
+use super::results::{check_sources, structure_results, structure_results_with_sources, RESULT_SCHEMA_PROMPT, SOURCES_SCHEMA_PROMPT};
+use super::ratelimit::{RateLimitStatus, RateLimiter};
+use super::robots::{self, ROBOTS_AGENT};
+use crate::config::{ApiStyle, Config, RetryJitter, RetryOn, TimeContextMode};
+use crate::error::{classify_status, error_chain, http_error_code, ApiError, GrokError, Result};
+use chrono::{DateTime, Local, NaiveDate, Offset, TimeZone, Utc};
+use chrono_tz::Tz;
+use futures::stream::{self, StreamExt};
 use rand::Rng;
-use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER, USER_AGENT};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tracing::warn;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
 
-const CONNECT_TIMEOUT: u64 = 10;
-const READ_TIMEOUT: u64 = 30;
-const REQUEST_TIMEOUT: u64 = 120;
-const MAX_CONTENT_BYTES: usize = 10 * 1024 * 1024;
+pub const REQUEST_TIMEOUT: u64 = 120;
+const ROBOTS_TIMEOUT: Duration = Duration::from_secs(10);
+const PROGRESS_BYTES: usize = 4096;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+/// Starts every note appended to cut-off content, e.g. `[truncated: exceeded 10 MB]`.
+const TRUNCATION_MARKER: &str = "\n\n[truncated: ";
+/// How long a fetch `continue_token` stays valid, and how many are kept at once.
+const CONTINUATION_TTL: Duration = Duration::from_secs(3600);
+const MAX_CONTINUATIONS: usize = 256;
+/// Characters of already delivered content quoted back to the model to resume after.
+const CONTINUATION_TAIL_CHARS: usize = 1000;
+/// Routing headers of the OpenAI-compatible API, set from `GROK_ORG_ID` / `GROK_PROJECT_ID`.
+const ORG_HEADER: &str = "openai-organization";
+const PROJECT_HEADER: &str = "openai-project";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConnectionTestResult {
@@ -24,81 +48,833 @@ pub struct ConnectionTestResult {
     pub error_code: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    /// What to check next for a failed test, e.g. the API key on `AUTH_ERROR`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<&'static str>,
+}
+
+impl ConnectionTestResult {
+    fn failure(code: &str, message: String, response_time_ms: Option<u64>) -> Self {
+        Self {
+            status: "error".into(),
+            response_time_ms,
+            model_count: None,
+            error_code: Some(code.into()),
+            message: Some(message),
+            hint: remediation_hint(code),
+        }
+    }
+}
+
+/// First-time setup advice for the error classes a connection test can hit.
+fn remediation_hint(code: &str) -> Option<&'static str> {
+    match code {
+        "AUTH_ERROR" => Some("The API rejected the key: check GROK_API_KEY (or GROK_API_KEY_FILE) and that it is valid for GROK_API_URL"),
+        "TLS_ERROR" => Some(
+            "The TLS handshake failed. An HTTPS-intercepting proxy is the usual cause: route through it explicitly with \
+            GROK_HTTPS_PROXY instead. Also check that GROK_API_URL uses the right scheme and port"
+        ),
+        "CONNECTION_FAILURE" => Some("Could not connect: check the host and port in GROK_API_URL, and set GROK_HTTPS_PROXY if a proxy is required"),
+        "TIMEOUT" => Some("No response in time: check network access to GROK_API_URL, or raise GROK_CONNECT_TIMEOUT"),
+        "NOT_FOUND" => Some("The /models endpoint was not found: check the path in GROK_API_URL (see GROK_API_PATH_STYLE)"),
+        "UNEXPECTED_RESPONSE" => Some("Something other than the API answered: check GROK_API_URL and any proxy or gateway login"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub prompt_tokens: u64,
+    #[serde(default)]
+    pub completion_tokens: u64,
+    #[serde(default)]
+    pub total_tokens: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GrokResponse {
+    pub content: String,
+    pub usage: Option<Usage>,
+    pub model: String,
+    /// Set on a truncated fetch; pass it back to `fetch_continue` for the next chunk.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continue_token: Option<String>,
+    /// Cut short by a stream timeout, `GROK_STREAM_MAX_SECS` or the size cap; never cached.
+    #[serde(skip)]
+    pub partial: bool,
+}
+
+/// A coarse progress update for a long-running request.
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub progress: f64,
+    pub total: Option<f64>,
+    pub message: String,
+}
+
+pub type ProgressSender = UnboundedSender<Progress>;
+
+/// Per-call transport settings; unlike `SearchOptions` they never change what is asked.
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    pub progress: Option<ProgressSender>,
+    /// Overrides the client-wide request timeout for this call.
+    pub timeout: Option<Duration>,
+    /// Send every content delta as a progress message instead of periodic byte counts.
+    pub stream_deltas: bool,
+    /// Cancelled when the MCP client cancels the tool call; aborts the request, retries included.
+    pub cancel: Option<CancellationToken>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FetchFormat {
+    #[default]
+    Markdown,
+    Text,
+    Html,
+}
+
+impl FetchFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "markdown" | "" => Some(Self::Markdown),
+            "text" => Some(Self::Text),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    fn system_prompt(self) -> &'static str {
+        match self {
+            Self::Markdown => FETCH_PROMPT,
+            Self::Text => FETCH_TEXT_PROMPT,
+            Self::Html => FETCH_HTML_PROMPT,
+        }
+    }
+
+    fn instruction(self) -> &'static str {
+        match self {
+            Self::Markdown => "获取该网页内容并返回其结构化Markdown格式",
+            Self::Text => "获取该网页内容并返回其纯文本格式",
+            Self::Html => "获取该网页内容并返回其原始HTML源码",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FetchMode {
+    #[default]
+    Full,
+    /// A short bulleted summary plus key links instead of the full page.
+    Summary,
+}
+
+impl FetchMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "full" | "" => Some(Self::Full),
+            "summary" => Some(Self::Summary),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FetchOptions {
+    pub format: FetchFormat,
+    pub mode: FetchMode,
+    /// Per-call model, overriding `GROK_FETCH_MODEL` and the configured model.
+    pub model: Option<String>,
+    /// Approximate character budget; the model is asked to fit it and the result is hard-capped.
+    pub max_chars: Option<usize>,
+    /// Check the site's robots.txt before spending an API request on the page.
+    pub respect_robots: bool,
+    /// Only extract the page region matching this CSS selector.
+    pub css_selector: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SortOrder {
+    #[default]
+    Relevance,
+    Recency,
+}
+
+impl SortOrder {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "relevance" | "" => Some(Self::Relevance),
+            "recency" => Some(Self::Recency),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SafeSearch {
+    Off,
+    #[default]
+    Moderate,
+    Strict,
+}
+
+impl SafeSearch {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "off" => Some(Self::Off),
+            "moderate" | "" => Some(Self::Moderate),
+            "strict" => Some(Self::Strict),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FetchOutcome {
+    pub url: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<&'static str>,
+    pub content: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continue_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct SearchOptions {
+    /// Platforms to focus on, as named in the prompt.
+    pub platforms: Vec<String>,
+    pub min_results: u32,
+    pub max_results: u32,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub exclude_domains: Vec<String>,
+    pub include_domains: Vec<String>,
+    pub language: Option<String>,
+    pub sort: SortOrder,
+    pub safe_search: SafeSearch,
+    pub strict_json: bool,
+    /// Ask for a top-level `sources` bibliography alongside the results.
+    pub include_sources: bool,
+    pub dedupe: bool,
+    /// Per-call model, overriding `GROK_SEARCH_MODEL` and the configured model.
+    pub model: Option<String>,
+    /// Earlier conversation turns as `(role, content)`, sent between the system prompt and the query.
+    pub context: Vec<(String, String)>,
 }
 
 #[derive(Debug, Clone)]
 pub struct GrokClient {
     client: reqwest::Client,
     base_url: String,
-    model: String,
+    /// Model for search calls: `GROK_SEARCH_MODEL`, else the configured model.
+    search_model: String,
+    /// Model for fetch calls: `GROK_FETCH_MODEL`, else the configured model.
+    fetch_model: String,
     retry_max_attempts: u32,
     retry_multiplier: f64,
     retry_max_wait: u64,
+    retry_jitter: RetryJitter,
+    retry_on: Vec<RetryOn>,
+    api_style: ApiStyle,
+    /// Longest wait for the next stream chunk.
+    read_timeout: Duration,
+    /// Longest wait for new content in a stream, ignoring keepalives; `None` when disabled.
+    stream_stall: Option<Duration>,
+    /// Longest a stream may run before its partial content is returned; `None` when disabled.
+    stream_max: Option<Duration>,
+    /// Return the content received so far instead of an error when a stream times out.
+    partial_on_timeout: bool,
+    streaming: bool,
+    dry_run: bool,
+    /// Log a length/hash placeholder instead of query text.
+    redact_queries: bool,
+    time_context: TimeContextMode,
+    time_keywords: Vec<String>,
+    timezone: Option<Tz>,
+    time_format: Option<String>,
+    fetch_concurrency: usize,
+    /// Caps in-flight API requests across all clones; `None` when unlimited.
+    limiter: Option<Arc<Semaphore>>,
+    /// Bounds the request rate across all clones; `None` when unlimited (`GROK_MAX_QPS=0`).
+    rate_limiter: Option<RateLimiter>,
+    breaker: CircuitBreaker,
+    metrics: Arc<Metrics>,
+    /// System prompt for searches: `GROK_SEARCH_PROMPT_FILE`, else the built-in `SEARCH_PROMPT`.
+    search_prompt: Arc<str>,
+    /// Replacement for the built-in Markdown fetch prompt from `GROK_FETCH_PROMPT_FILE`.
+    fetch_prompt: Option<Arc<str>>,
+    /// Extra instructions appended to every search / fetch request (`GROK_SEARCH_SUFFIX`, `GROK_FETCH_SUFFIX`).
+    search_suffix: Option<String>,
+    fetch_suffix: Option<String>,
+    /// Plain client for requests to fetched sites (robots.txt), without the API key.
+    site_client: reqwest::Client,
+    max_content_bytes: usize,
+    /// Fetch results shorter than this are retried once, then rejected (`GROK_FETCH_MIN_CHARS`, 0 disables).
+    fetch_min_chars: usize,
+    /// Delay before racing a second streaming request against a slow first one (`GROK_HEDGE_DELAY_MS`).
+    hedge_delay: Option<Duration>,
+    /// Retry a search once with a broadening nudge when it comes back empty (`GROK_RETRY_EMPTY_RESULTS`).
+    retry_empty_results: bool,
+    cache_ttl: Duration,
+    search_cache: Arc<Mutex<HashMap<u64, (Instant, GrokResponse)>>>,
+    /// Fetched pages keyed by normalized URL and fetch options (`GROK_FETCH_CACHE_TTL`).
+    fetch_cache_ttl: Duration,
+    fetch_cache: Arc<Mutex<HashMap<u64, (Instant, GrokResponse)>>>,
+    /// Where each truncated fetch left off, keyed by its `continue_token`.
+    continuations: Arc<Mutex<HashMap<String, Continuation>>>,
+}
+
+/// The state behind a fetch `continue_token`: the page, its options and the end of what was delivered.
+#[derive(Debug, Clone)]
+struct Continuation {
+    url: String,
+    opts: FetchOptions,
+    tail: String,
+    created: Instant,
 }
 
 impl GrokClient {
     pub fn new(config: &Config) -> Self {
         let mut headers = HeaderMap::new();
         headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", config.api_key.trim())).unwrap());
+        // Accept is set per request instead: event streams for streaming calls, JSON otherwise.
         headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-        headers.insert(ACCEPT, HeaderValue::from_static("text/event-stream"));
-        headers.insert(USER_AGENT, HeaderValue::from_str(&format!("grok-search-mcp/{}", env!("CARGO_PKG_VERSION"))).unwrap());
+        // GROK_USER_AGENT is validated in Config::load.
+        if let Ok(agent) = HeaderValue::from_str(&config.user_agent) {
+            headers.insert(USER_AGENT, agent);
+        }
+        // Org and project IDs are validated in Config::load; extra headers may still override them.
+        for (name, value) in [(ORG_HEADER, &config.org_id), (PROJECT_HEADER, &config.project_id)] {
+            if let Some(value) = value.as_deref().and_then(|v| HeaderValue::from_str(v).ok()) {
+                headers.insert(HeaderName::from_static(name), value);
+            }
+        }
+        // Extra headers are validated in Config::load.
+        for (name, value) in &config.extra_headers {
+            if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+                headers.insert(name, value);
+            }
+        }
 
-        let client = reqwest::Client::builder()
+        // gzip/brotli advertise Accept-Encoding and decode bodies transparently, streams included,
+        // so the SSE decoder always sees plain text.
+        let mut builder = reqwest::Client::builder()
             .default_headers(headers)
-            .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT))
-            .timeout(Duration::from_secs(REQUEST_TIMEOUT))
-            .build()
-            .expect("failed to build HTTP client");
+            .gzip(true)
+            .brotli(true)
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT));
+        // Proxy URLs are validated in Config::load; credentials in the URL are used for proxy auth.
+        if let Some(proxy) = &config.http_proxy {
+            builder = builder.proxy(reqwest::Proxy::http(proxy).expect("invalid GROK_HTTP_PROXY"));
+        }
+        if let Some(proxy) = &config.https_proxy {
+            builder = builder.proxy(reqwest::Proxy::https(proxy).expect("invalid GROK_HTTPS_PROXY"));
+        }
+        let client = builder.build().expect("failed to build HTTP client");
+
+        let mut site_builder = reqwest::Client::builder()
+            .user_agent(format!("{ROBOTS_AGENT}/{}", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .timeout(ROBOTS_TIMEOUT);
+        if let Some(proxy) = &config.http_proxy {
+            site_builder = site_builder.proxy(reqwest::Proxy::http(proxy).expect("invalid GROK_HTTP_PROXY"));
+        }
+        if let Some(proxy) = &config.https_proxy {
+            site_builder = site_builder.proxy(reqwest::Proxy::https(proxy).expect("invalid GROK_HTTPS_PROXY"));
+        }
+        let site_client = site_builder.build().expect("failed to build HTTP client");
 
         Self {
             client,
-            base_url: config.api_url.clone(),
-            model: config.model.clone(),
+            base_url: config.api_base(),
+            search_model: config.search_model.clone().unwrap_or_else(|| config.model.clone()),
+            fetch_model: config.fetch_model.clone().unwrap_or_else(|| config.model.clone()),
             retry_max_attempts: config.retry_max_attempts,
             retry_multiplier: config.retry_multiplier,
             retry_max_wait: config.retry_max_wait,
+            retry_jitter: config.retry_jitter,
+            retry_on: config.retry_on.clone(),
+            api_style: config.api_style,
+            read_timeout: Duration::from_secs(config.read_timeout_secs),
+            stream_stall: (config.stream_stall_secs > 0).then(|| Duration::from_secs(config.stream_stall_secs)),
+            stream_max: (config.stream_max_secs > 0).then(|| Duration::from_secs(config.stream_max_secs)),
+            partial_on_timeout: config.partial_on_timeout,
+            streaming: config.streaming,
+            dry_run: config.dry_run,
+            redact_queries: config.redact_queries,
+            time_context: config.time_context,
+            time_keywords: config.time_keywords.iter().map(|kw| kw.to_lowercase()).collect(),
+            timezone: config.timezone.as_deref().and_then(|name| match name.parse::<Tz>() {
+                Ok(tz) => Some(tz),
+                Err(_) => {
+                    warn!("Unknown GROK_TIMEZONE '{}', using the local timezone", name);
+                    None
+                }
+            }),
+            time_format: config.time_format.clone(),
+            fetch_concurrency: config.fetch_concurrency as usize,
+            metrics: Arc::new(Metrics::default()),
+            breaker: CircuitBreaker::new(config.circuit_threshold, Duration::from_secs(config.circuit_cooldown_secs)),
+            limiter: (config.max_concurrency > 0).then(|| Arc::new(Semaphore::new(config.max_concurrency as usize))),
+            rate_limiter: (config.max_qps > 0.0).then(|| RateLimiter::new(config.max_qps)),
+            search_prompt: config.search_prompt.as_deref().unwrap_or(SEARCH_PROMPT).into(),
+            fetch_prompt: config.fetch_prompt.as_deref().map(Into::into),
+            search_suffix: config.search_suffix.clone(),
+            fetch_suffix: config.fetch_suffix.clone(),
+            site_client,
+            max_content_bytes: config.max_content_mb as usize * 1024 * 1024,
+            fetch_min_chars: config.fetch_min_chars as usize,
+            hedge_delay: (config.hedge_delay_ms > 0).then(|| Duration::from_millis(config.hedge_delay_ms)),
+            retry_empty_results: config.retry_empty_results,
+            cache_ttl: Duration::from_secs(config.cache_ttl_secs),
+            search_cache: Arc::new(Mutex::new(HashMap::new())),
+            fetch_cache_ttl: Duration::from_secs(config.fetch_cache_ttl_secs),
+            fetch_cache: Arc::new(Mutex::new(HashMap::new())),
+            continuations: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
-    pub async fn search(&self, query: &str, platform: &str, min_results: u32, max_results: u32) -> Result<String> {
+    pub async fn search(&self, query: &str, opts: &SearchOptions, call: &CallOptions) -> Result<GrokResponse> {
+        let cache_key = search_cache_key(query, opts);
+        if !self.dry_run
+            && let Some(cached) = cache_lookup(&self.search_cache, self.cache_ttl, cache_key)
+        {
+            debug!("Search cache hit for query: {}", self.redact(query));
+            return Ok(cached);
+        }
+
         let mut user_content = String::new();
-        if needs_time_context(query) {
-            user_content.push_str(&time_context());
+        let inject_time = match self.time_context {
+            TimeContextMode::Always => true,
+            TimeContextMode::Never => false,
+            TimeContextMode::Auto => {
+                needs_time_context(query, &self.time_keywords) || opts.start_date.is_some() || opts.end_date.is_some()
+            }
+        };
+        if inject_time {
+            user_content.push_str(&time_context(self.timezone, self.time_format.as_deref()));
         }
         user_content.push_str(query);
 
-        if !platform.trim().is_empty() {
+        if !opts.platforms.is_empty() {
+            user_content.push_str(&format!(
+                "\n\nYou should search the web for the information you need, and focus on these platforms: {}",
+                opts.platforms.join(", ")
+            ));
+        }
+        if let Some(constraint) = date_range_constraint(opts.start_date, opts.end_date) {
+            user_content.push_str(&format!("\n\n{constraint}"));
+        }
+        // An allowlist already rules out everything else, so the exclude list is redundant next to it.
+        if !opts.include_domains.is_empty() {
+            user_content.push_str(&format!(
+                "\n\nOnly return results from these domains: {}",
+                opts.include_domains.join(", ")
+            ));
+        } else if !opts.exclude_domains.is_empty() {
             user_content.push_str(&format!(
-                "\n\nYou should search the web for the information you need, and focus on these platform: {}",
-                platform.trim()
+                "\n\nExclude results from these domains: {}",
+                opts.exclude_domains.join(", ")
             ));
         }
-        if max_results > 0 {
+        if let Some(language) = &opts.language {
+            user_content.push_str(&format!("\n\nReturn only results written in {language}."));
+        }
+        match opts.sort {
+            SortOrder::Relevance => user_content.push_str(
+                "\n\nOrder the results by relevance to the query, most relevant first."
+            ),
+            SortOrder::Recency => user_content.push_str(
+                "\n\nOrder the results by publication date, most recent first, and include a \"published_at\" field (ISO 8601 date, or \"\" if unknown) in each result object."
+            ),
+        }
+        // Best effort: the API has no server-side filter, so this only shapes the prompt.
+        match opts.safe_search {
+            SafeSearch::Off => {}
+            SafeSearch::Moderate => user_content.push_str("\n\nExclude adult or sexually explicit content."),
+            SafeSearch::Strict => user_content.push_str(
+                "\n\nExclude adult, sexually explicit, graphically violent, or otherwise age-inappropriate content; every result must be suitable for a school audience."
+            ),
+        }
+        if opts.max_results > 0 {
             user_content.push_str(&format!(
                 "\n\nYou should return the results in a JSON format, and the results should at least be {} and at most be {} results.",
-                min_results, max_results
+                opts.min_results, opts.max_results
             ));
         }
+        if opts.include_sources {
+            user_content.push_str("\n\nReturn a JSON object with two arrays: `results`, the search results, and `sources`, every source you consulted or cite as {\"title\", \"url\"}, including those only mentioned inline.");
+        }
+        if let Some(suffix) = &self.search_suffix {
+            user_content.push_str(&format!("\n\n{suffix}"));
+        }
+
+        let start = Instant::now();
+        let system_prompt = if opts.strict_json && opts.include_sources {
+            format!("{}{}", self.search_prompt, *SOURCES_SCHEMA_PROMPT)
+        } else if opts.strict_json {
+            format!("{}{}", self.search_prompt, *RESULT_SCHEMA_PROMPT)
+        } else {
+            self.search_prompt.to_string()
+        };
+        let mut messages = search_messages(&system_prompt, &opts.context, &user_content);
+        let model = opts.model.as_deref().unwrap_or(&self.search_model);
+        let mut resp = self.chat(&messages, model, call).await?;
+        // The dry-run echo is not a search result, so none of the post-processing below applies.
+        if self.dry_run {
+            return Ok(resp);
+        }
+
+        // A single extra attempt, so a query that really has no results cannot loop.
+        if self.retry_empty_results && is_empty_result(&resp.content) {
+            warn!("Search returned no results, retrying once with a broader instruction");
+            user_content.push_str("\n\nIf you found nothing, broaden the query: try synonyms, related terms or a wider scope, and return the closest relevant results.");
+            messages = search_messages(&system_prompt, &opts.context, &user_content);
+            let retry = self.chat(&messages, model, call).await?;
+            let usage = sum_usage(resp.usage.take(), retry.usage.clone());
+            resp = GrokResponse { content: retry.content, usage, model: retry.model, continue_token: None, partial: retry.partial };
+        }
 
-        self.chat_stream(&user_content, SEARCH_PROMPT).await
+        if opts.strict_json {
+            let validate = |content: &str| {
+                let json = normalize_json(content)?;
+                if opts.include_sources {
+                    check_sources(&json)?;
+                }
+                Ok::<_, GrokError>(json)
+            };
+            match validate(&resp.content) {
+                Ok(json) => resp.content = json,
+                Err(e) => {
+                    warn!("Search returned invalid JSON ({}), asking the model to correct it", e);
+                    messages.push(serde_json::json!({ "role": "assistant", "content": resp.content }));
+                    messages.push(serde_json::json!({
+                        "role": "user",
+                        "content": format!(
+                            "Your previous response was not valid JSON ({e}). Return only the corrected JSON, with no code fences or other text."
+                        ),
+                    }));
+                    let retry = self.chat(&messages, model, call).await?;
+                    let usage = sum_usage(resp.usage.take(), retry.usage.clone());
+                    resp = GrokResponse {
+                        content: validate(&retry.content)?,
+                        usage,
+                        model: retry.model,
+                        continue_token: None,
+                        partial: retry.partial,
+                    };
+                }
+            }
+            resp.content = if opts.include_sources {
+                structure_results_with_sources(&resp.content)?
+            } else {
+                structure_results(&resp.content)?
+            };
+        }
+        if opts.dedupe
+            && let Some(deduped) = dedupe_results(&resp.content)
+        {
+            resp.content = deduped;
+        }
+        log_request("search", &self.redact(query), &resp, start.elapsed());
+        cache_store(&self.search_cache, self.cache_ttl, cache_key, &resp);
+        Ok(resp)
     }
 
-    pub async fn fetch(&self, url: &str) -> Result<String> {
-        let user_content = format!("{}\n获取该网页内容并返回其结构化Markdown格式", url.trim());
-        self.chat_stream(&user_content, FETCH_PROMPT).await
+    pub async fn fetch(&self, url: &str, opts: &FetchOptions, call: &CallOptions) -> Result<GrokResponse> {
+        if self.dry_run {
+            return self.fetch_chunk(url, opts, None, call).await;
+        }
+        let cache_key = fetch_cache_key(url, opts);
+        if let Some(cached) = cache_lookup(&self.fetch_cache, self.fetch_cache_ttl, cache_key) {
+            debug!("Fetch cache hit for URL: {}", url.trim());
+            return Ok(cached);
+        }
+        if opts.respect_robots {
+            self.check_robots(url.trim()).await?;
+        }
+        let resp = self.fetch_chunk(url, opts, None, call).await?;
+        cache_store(&self.fetch_cache, self.fetch_cache_ttl, cache_key, &resp);
+        Ok(resp)
+    }
+
+    /// Fetches the next chunk of a page whose earlier result was truncated, resuming after the
+    /// content delivered with `token`. The chunk carries a new token when it is truncated too.
+    pub async fn fetch_continue(&self, token: &str, call: &CallOptions) -> Result<GrokResponse> {
+        let token = token.trim();
+        let continuation = {
+            let continuations = self.continuations.lock().unwrap_or_else(|e| e.into_inner());
+            continuations.get(token).filter(|c| c.created.elapsed() < CONTINUATION_TTL).cloned()
+        };
+        let Some(continuation) = continuation else {
+            return Err(GrokError::ContinueTokenInvalid(token.to_string()));
+        };
+        debug!("Continuing fetch of {}", continuation.url);
+        self.fetch_chunk(&continuation.url, &continuation.opts, Some(&continuation.tail), call).await
+    }
+
+    /// One fetch request. With `resume_after`, the model is asked to continue past that passage
+    /// instead of starting at the top of the page.
+    async fn fetch_chunk(&self, url: &str, opts: &FetchOptions, resume_after: Option<&str>, call: &CallOptions) -> Result<GrokResponse> {
+        let (system_prompt, instruction) = match opts.mode {
+            FetchMode::Full => match (&self.fetch_prompt, opts.format) {
+                (Some(prompt), FetchFormat::Markdown) => (&**prompt, opts.format.instruction()),
+                _ => (opts.format.system_prompt(), opts.format.instruction()),
+            },
+            FetchMode::Summary => (FETCH_SUMMARY_PROMPT, "获取该网页内容并返回简短的要点摘要及关键链接"),
+        };
+        let mut user_content = format!("{}\n{}", url.trim(), instruction);
+        if let Some(selector) = &opts.css_selector {
+            user_content.push_str(&format!(
+                "\n\nOnly extract the part of the page matched by the CSS selector `{selector}` (the matching elements \
+                and their descendants) and return just that fragment, ignoring the rest of the page. \
+                If nothing matches the selector, say so instead of returning other content."
+            ));
+        }
+        if let Some(max_chars) = opts.max_chars {
+            user_content.push_str(&format!(
+                "\n\nKeep the result to roughly {max_chars} characters; summarize or shorten less important sections to fit."
+            ));
+        }
+        if let Some(tail) = resume_after {
+            user_content.push_str(&format!(
+                "\n\nAn earlier response already delivered this page up to the passage below. Continue from exactly \
+                where it ends, without repeating anything before it, through to the end of the page:\n<<<\n{tail}\n>>>"
+            ));
+        }
+        if let Some(suffix) = &self.fetch_suffix {
+            user_content.push_str(&format!("\n\n{suffix}"));
+        }
+        let start = Instant::now();
+        let model = opts.model.as_deref().unwrap_or(&self.fetch_model);
+        let mut resp = self.chat(&chat_messages(system_prompt, &user_content), model, call).await?;
+        if self.dry_run {
+            return Ok(resp);
+        }
+        // The last chunk of a long page may legitimately be short.
+        if resume_after.is_none() && self.is_too_short(&resp.content) {
+            warn!("Fetch of {} returned only {} characters, retrying with a stronger instruction", url.trim(), resp.content.trim().chars().count());
+            user_content.push_str("\n\nThe previous attempt returned almost nothing. Open the page and reproduce its full visible content; \
+                if the page cannot be read, say why instead of returning an empty result.");
+            let mut retry = self.chat(&chat_messages(system_prompt, &user_content), model, call).await?;
+            // Both attempts are billed, so report their combined usage.
+            retry.usage = sum_usage(resp.usage.take(), retry.usage.take());
+            resp = retry;
+            if self.is_too_short(&resp.content) {
+                return Err(GrokError::ContentTooShort {
+                    url: url.trim().to_string(),
+                    chars: resp.content.trim().chars().count(),
+                    min_chars: self.fetch_min_chars,
+                });
+            }
+        }
+        if let Some(max_chars) = opts.max_chars {
+            truncate_chars(&mut resp.content, max_chars);
+        }
+        if let Some(tail) = truncated_tail(&resp.content) {
+            resp.continue_token = Some(self.store_continuation(url, opts, tail));
+        }
+        log_request("fetch", url, &resp, start.elapsed());
+        Ok(resp)
+    }
+
+    /// Records where a truncated fetch stopped and returns the token to resume it. Expired
+    /// entries are dropped first, then the oldest ones beyond `MAX_CONTINUATIONS`.
+    fn store_continuation(&self, url: &str, opts: &FetchOptions, tail: String) -> String {
+        let token = format!("{:016x}", rand::random::<u64>());
+        let mut continuations = self.continuations.lock().unwrap_or_else(|e| e.into_inner());
+        continuations.retain(|_, c| c.created.elapsed() < CONTINUATION_TTL);
+        while continuations.len() >= MAX_CONTINUATIONS {
+            let Some(oldest) = continuations.iter().min_by_key(|(_, c)| c.created).map(|(token, _)| token.clone()) else { break };
+            continuations.remove(&oldest);
+        }
+        continuations.insert(token.clone(), Continuation {
+            url: url.trim().to_string(),
+            opts: opts.clone(),
+            tail,
+            created: Instant::now(),
+        });
+        token
+    }
+
+    /// Whether fetched content falls below `GROK_FETCH_MIN_CHARS`.
+    fn is_too_short(&self, content: &str) -> bool {
+        self.fetch_min_chars > 0 && content.trim().chars().count() < self.fetch_min_chars
+    }
+
+    /// Query text as it may appear in logs: unchanged, or with `GROK_REDACT_QUERIES` a placeholder
+    /// carrying only the length and a short hash, so repeated queries can still be correlated.
+    fn redact(&self, query: &str) -> String {
+        if !self.redact_queries {
+            return query.to_string();
+        }
+        let mut hasher = DefaultHasher::new();
+        query.hash(&mut hasher);
+        format!("<redacted chars={} hash={:08x}>", query.chars().count(), hasher.finish() as u32)
+    }
+
+    /// Refuses `url` when its site's robots.txt disallows it for our agent. A missing robots.txt
+    /// (4xx) allows everything; an unreachable one (5xx or network error) disallows everything, per RFC 9309.
+    async fn check_robots(&self, url: &str) -> Result<()> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| GrokError::InvalidParams(format!("invalid URL {url}: {e}")))?;
+        let Some(robots_url) = robots::robots_url(&parsed) else { return Ok(()) };
+        let disallowed = || GrokError::RobotsDisallowed { url: url.to_string(), robots_url: robots_url.to_string() };
+
+        let resp = match self.site_client.get(robots_url.clone()).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                warn!("Could not read {}, treating the site as disallowed: {}", robots_url, e);
+                return Err(disallowed());
+            }
+        };
+        let status = resp.status();
+        if status.is_client_error() {
+            debug!("No robots.txt at {} ({}), allowing fetch", robots_url, status);
+            return Ok(());
+        }
+        if !status.is_success() {
+            warn!("robots.txt at {} returned {}, treating the site as disallowed", robots_url, status);
+            return Err(disallowed());
+        }
+        let body = match resp.text().await {
+            Ok(body) => body,
+            Err(e) => {
+                warn!("Could not read {}, treating the site as disallowed: {}", robots_url, e);
+                return Err(disallowed());
+            }
+        };
+        if robots::is_allowed(&body, &robots::request_path(&parsed)) {
+            Ok(())
+        } else {
+            Err(disallowed())
+        }
+    }
+
+    /// Runs a bare search against `model` without touching the cache, for comparing models.
+    pub async fn probe(&self, model: &str, query: &str) -> Result<GrokResponse> {
+        let start = Instant::now();
+        let resp = self.chat(&chat_messages(&self.search_prompt, query), model, &CallOptions::default()).await?;
+        log_request("probe", &self.redact(query), &resp, start.elapsed());
+        Ok(resp)
+    }
+
+    /// Fetches several URLs with bounded concurrency. Failures are reported per URL
+    /// and never abort the batch; results keep the input order. Progress counts completed URLs,
+    /// and the timeout applies to each URL separately.
+    pub async fn fetch_many(&self, urls: &[String], opts: &FetchOptions, call: &CallOptions) -> Vec<FetchOutcome> {
+        let total = urls.len();
+        let per_url = CallOptions { progress: None, timeout: call.timeout, stream_deltas: false, cancel: call.cancel.clone() };
+        let mut outcomes: Vec<(usize, FetchOutcome)> = Vec::with_capacity(total);
+        let mut pending = stream::iter(urls.iter().cloned().enumerate())
+            .map(|(idx, url)| {
+                let per_url = &per_url;
+                async move {
+                    let outcome = match self.fetch(&url, opts, per_url).await {
+                        Ok(resp) => FetchOutcome {
+                            url,
+                            status: "success".into(),
+                            error_code: None,
+                            content: resp.content,
+                            continue_token: resp.continue_token,
+                        },
+                        Err(e) => FetchOutcome {
+                            url,
+                            status: "error".into(),
+                            error_code: Some(e.code()),
+                            content: e.to_string(),
+                            continue_token: None,
+                        },
+                    };
+                    (idx, outcome)
+                }
+            })
+            .buffer_unordered(self.fetch_concurrency.max(1));
+
+        while let Some(item) = pending.next().await {
+            outcomes.push(item);
+            if let Some(tx) = &call.progress {
+                let _ = tx.send(Progress {
+                    progress: outcomes.len() as f64,
+                    total: Some(total as f64),
+                    message: format!("fetched {}/{} URLs", outcomes.len(), total),
+                });
+            }
+        }
+        outcomes.sort_by_key(|(idx, _)| *idx);
+        outcomes.into_iter().map(|(_, outcome)| outcome).collect()
+    }
+
+    /// Carries metrics over from a previous client so counters survive a config reload.
+    pub fn with_metrics_from(mut self, previous: &GrokClient) -> Self {
+        self.metrics = previous.metrics.clone();
+        self
+    }
+
+    /// Request metrics in the Prometheus text exposition format.
+    pub fn render_metrics(&self) -> String {
+        self.metrics.render()
+    }
+
+    pub fn circuit_status(&self) -> CircuitStatus {
+        self.breaker.status()
+    }
+
+    /// `None` when `GROK_MAX_QPS` is unset.
+    pub fn rate_limit_status(&self) -> Option<RateLimitStatus> {
+        self.rate_limiter.as_ref().map(RateLimiter::status)
+    }
+
+    /// Drops every cached search result and fetched page and returns how many entries were removed.
+    pub fn clear_cache(&self) -> usize {
+        [&self.search_cache, &self.fetch_cache].into_iter().map(|cache| {
+            let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+            let evicted = cache.len();
+            cache.clear();
+            evicted
+        }).sum()
+    }
+
+    /// Returns the model IDs advertised by `/models`, sorted alphabetically.
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        let url = format!("{}/models", self.base_url);
+        self.with_retry(|| self.try_list_models(&url)).await
+    }
+
+    async fn try_list_models(&self, url: &str) -> Result<Vec<String>> {
+        let _permit = self.acquire_permit().await;
+        let resp = self.client.get(url).header(ACCEPT, "application/json").send().await.map_err(map_err)?;
+        if !resp.status().is_success() {
+            return Err(error_from_response(resp).await);
+        }
+
+        let v: serde_json::Value = ensure_json(resp).await?.json().await.map_err(map_err)?;
+        let mut models: Vec<String> = v.get("data")
+            .and_then(|d| d.as_array())
+            .map(|items| items.iter().filter_map(|m| m.get("id").and_then(|id| id.as_str())).map(String::from).collect())
+            .unwrap_or_default();
+        models.sort();
+        models.dedup();
+        Ok(models)
     }
 
     pub async fn test_connection(&self) -> ConnectionTestResult {
         let url = format!("{}/models", self.base_url);
         let start = Instant::now();
 
-        match self.client.get(&url).send().await {
+        match self.client.get(&url).header(ACCEPT, "application/json").send().await {
             Ok(resp) => {
                 let elapsed = start.elapsed().as_millis() as u64;
                 let status = resp.status();
 
                 if status.is_success() {
+                    let resp = match ensure_json(resp).await {
+                        Ok(resp) => resp,
+                        Err(e) => return ConnectionTestResult::failure(e.code(), e.to_string(), Some(elapsed)),
+                    };
                     match resp.json::<serde_json::Value>().await {
                         Ok(v) => ConnectionTestResult {
                             status: "success".into(),
@@ -106,178 +882,1115 @@ impl GrokClient {
                             model_count: v.get("data").and_then(|d| d.as_array()).map(|a| a.len()),
                             error_code: None,
                             message: Some(format!("OK (HTTP {})", status.as_u16())),
+                            hint: None,
                         },
-                        Err(e) => ConnectionTestResult {
-                            status: "error".into(),
-                            response_time_ms: Some(elapsed),
-                            model_count: None,
-                            error_code: Some("PARSE_ERROR".into()),
-                            message: Some(e.to_string()),
-                        },
+                        Err(e) => ConnectionTestResult::failure("PARSE_ERROR", e.to_string(), Some(elapsed)),
                     }
                 } else {
                     let code = status.as_u16();
-                    ConnectionTestResult {
-                        status: "error".into(),
-                        response_time_ms: Some(elapsed),
-                        model_count: None,
-                        error_code: Some(classify_status(code)),
-                        message: Some(format!("HTTP {}", code)),
-                    }
+                    ConnectionTestResult::failure(classify_status(code), format!("HTTP {}", code), Some(elapsed))
                 }
             }
-            Err(e) => ConnectionTestResult {
-                status: "error".into(),
-                response_time_ms: None,
-                model_count: None,
-                error_code: Some(if e.is_timeout() { "TIMEOUT" } else if e.is_connect() { "CONNECTION_FAILURE" } else { "NETWORK_ERROR" }.into()),
-                message: Some(e.to_string()),
+            Err(e) => ConnectionTestResult::failure(http_error_code(&e), error_chain(&e), None),
+        }
+    }
+
+    async fn chat(&self, messages: &[serde_json::Value], model: &str, call: &CallOptions) -> Result<GrokResponse> {
+        if self.dry_run {
+            return self.dry_run_response(messages, model);
+        }
+        let request = async {
+            if self.streaming {
+                self.chat_stream(messages, model, call).await
+            } else {
+                self.chat_once(messages, model, call).await
+            }
+        };
+        let Some(cancel) = &call.cancel else { return request.await };
+        // Dropping the request future closes the connection, so the stream stops mid-read.
+        tokio::select! {
+            result = request => result,
+            _ = cancel.cancelled() => {
+                info!("Request cancelled by the client, aborting the API call");
+                Err(GrokError::Cancelled)
+            }
+        }
+    }
+
+    async fn chat_stream(&self, messages: &[serde_json::Value], model: &str, call: &CallOptions) -> Result<GrokResponse> {
+        let url = self.chat_url();
+        let payload = self.chat_payload(messages, model, true);
+        // The client already holds the deltas of a failed attempt; a retry would append a second copy.
+        let forwarded = AtomicBool::new(false);
+        self.with_retry_while(
+            || self.try_stream_hedged(&url, &payload, call, &forwarded),
+            || !forwarded.load(Ordering::Relaxed),
+        )
+        .await
+    }
+
+    /// One streaming attempt. With `GROK_HEDGE_DELAY_MS`, a second identical request starts when the
+    /// first has not even returned its response headers after the delay, and the first success wins.
+    /// A first stream that has started responding is healthy and is never hedged, however long it runs.
+    /// The loser is dropped, which aborts its request, so nothing outlives the call. Calls that forward
+    /// progress are not hedged, since two streams would interleave their notifications.
+    async fn try_stream_hedged(
+        &self,
+        url: &str,
+        payload: &serde_json::Value,
+        call: &CallOptions,
+        forwarded: &AtomicBool,
+    ) -> Result<GrokResponse> {
+        let delay = match self.hedge_delay {
+            Some(delay) if call.progress.is_none() => delay,
+            _ => return self.try_stream_request(url, payload, call, None, forwarded).await,
+        };
+        let responded = Notify::new();
+        let first = self.try_stream_request(url, payload, call, Some(&responded), forwarded);
+        tokio::pin!(first);
+        tokio::select! {
+            result = &mut first => return result,
+            _ = responded.notified() => return first.await,
+            _ = tokio::time::sleep(delay) => {}
+        }
+
+        debug!("No response after {:?}, sending a hedged request", delay);
+        self.metrics.record_hedge();
+        let second = self.try_stream_request(url, payload, call, None, forwarded);
+        tokio::pin!(second);
+        tokio::select! {
+            result = &mut first => match result {
+                Ok(resp) => Ok(resp),
+                Err(_) => second.await,
+            },
+            result = &mut second => match result {
+                Ok(resp) => Ok(resp),
+                Err(_) => first.await,
             },
         }
     }
 
-    async fn chat_stream(&self, user_content: &str, system_prompt: &str) -> Result<String> {
-        let url = format!("{}/chat/completions", self.base_url);
-        let payload = serde_json::json!({
-            "model": self.model,
-            "messages": [
-                { "role": "system", "content": system_prompt },
-                { "role": "user", "content": user_content },
-            ],
-            "stream": true
+    /// Single-shot completion without SSE parsing; useful as a baseline when debugging the stream decoder.
+    async fn chat_once(&self, messages: &[serde_json::Value], model: &str, call: &CallOptions) -> Result<GrokResponse> {
+        let url = self.chat_url();
+        let payload = self.chat_payload(messages, model, false);
+        self.with_retry(|| self.try_once_request(&url, &payload, call)).await
+    }
+
+    /// Returns the request that would have been sent, without calling the API.
+    fn dry_run_response(&self, messages: &[serde_json::Value], model: &str) -> Result<GrokResponse> {
+        let request = serde_json::json!({
+            "dry_run": true,
+            "endpoint": self.chat_url(),
+            "model": model,
+            "messages": messages,
         });
+        Ok(GrokResponse {
+            content: serde_json::to_string_pretty(&request)?,
+            usage: None,
+            model: model.to_string(),
+            continue_token: None,
+            partial: false,
+        })
+    }
+
+    fn chat_url(&self) -> String {
+        match self.api_style {
+            ApiStyle::Chat => format!("{}/chat/completions", self.base_url),
+            ApiStyle::Responses => format!("{}/responses", self.base_url),
+        }
+    }
 
+    fn chat_payload(&self, messages: &[serde_json::Value], model: &str, stream: bool) -> serde_json::Value {
+        match self.api_style {
+            ApiStyle::Chat => {
+                let mut payload = serde_json::json!({
+                    "model": model,
+                    "messages": messages,
+                    "stream": stream
+                });
+                if stream {
+                    payload["stream_options"] = serde_json::json!({ "include_usage": true });
+                }
+                payload
+            }
+            // /responses accepts the same role/content items as `input` and always reports usage.
+            ApiStyle::Responses => serde_json::json!({
+                "model": model,
+                "input": messages,
+                "stream": stream
+            }),
+        }
+    }
+
+    async fn with_retry<T, F, Fut>(&self, op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.with_retry_while(op, || true).await
+    }
+
+    /// `with_retry`, except that a failure is final once `can_retry` turns false, as if the error
+    /// were not retryable.
+    async fn with_retry_while<T, F, Fut>(&self, mut op: F, can_retry: impl Fn() -> bool) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let budget_started = Instant::now();
+        let mut attempts_detail = Vec::new();
         let mut last_err = String::new();
+        let mut last_code = "";
         for attempt in 0..=self.retry_max_attempts {
-            match self.try_stream_request(&url, &payload).await {
-                Ok(content) => return Ok(content),
+            if let Err(e) = self.breaker.check() {
+                // Tripped by an earlier attempt of this call: keep the attempts that led here.
+                if attempts_detail.is_empty() {
+                    return Err(e);
+                }
+                return Err(GrokError::AfterRetries { error: Box::new(e), attempts_detail, elapsed: budget_started.elapsed() });
+            }
+            let started = Instant::now();
+            let result = op().await;
+            let took = started.elapsed();
+            self.metrics.record_request(took);
+            if let Err(e) = &result {
+                self.metrics.record_failure(e.kind());
+            }
+            // Errors that say nothing about the backend's health (a 401, a bad request) leave the count alone.
+            match &result {
+                Ok(_) => self.breaker.record_success(),
+                Err(e) if is_outage(e) => self.breaker.record_failure(),
+                Err(_) => {}
+            }
+            match result {
+                Ok(value) => return Ok(value),
                 Err(e) => {
-                    if !is_retryable(&e) || attempt >= self.retry_max_attempts {
-                        if attempt >= self.retry_max_attempts {
-                            return Err(GrokError::MaxRetries { attempts: self.retry_max_attempts + 1, last_error: e.to_string() });
-                        }
+                    let retryable = is_retryable(&e, &self.retry_on) && can_retry();
+                    if !retryable && attempt == 0 {
                         return Err(e);
                     }
                     last_err = e.to_string();
-                    let delay = self.backoff(attempt);
+                    last_code = e.code();
+                    attempts_detail.push((attempt + 1, format!("[{}] {} ({} ms)", last_code, last_err, took.as_millis())));
+                    // Retries were not exhausted, so the error keeps its own identity.
+                    if !retryable {
+                        return Err(GrokError::AfterRetries {
+                            error: Box::new(e),
+                            attempts_detail,
+                            elapsed: budget_started.elapsed(),
+                        });
+                    }
+                    if attempt >= self.retry_max_attempts {
+                        break;
+                    }
+                    // Prefer the server's Retry-After hint, still bounded by the configured max wait.
+                    let delay = match &e {
+                        GrokError::RateLimited { retry_after: Some(after), .. } => (*after).min(Duration::from_secs(self.retry_max_wait)),
+                        _ => self.backoff(attempt),
+                    };
                     warn!("Grok API error, retrying in {:?} (attempt {}/{})", delay, attempt + 1, self.retry_max_attempts + 1);
+                    self.metrics.record_retry();
                     tokio::time::sleep(delay).await;
                 }
             }
         }
-        Err(GrokError::MaxRetries { attempts: self.retry_max_attempts + 1, last_error: last_err })
+        Err(GrokError::MaxRetries {
+            attempts: attempts_detail.len() as u32,
+            last_error: last_err,
+            last_code,
+            attempts_detail,
+            elapsed: budget_started.elapsed(),
+        })
+    }
+
+    /// Waits for a `GROK_MAX_QPS` token, then for a request slot when `GROK_MAX_CONCURRENCY` is set.
+    /// Taken per attempt, so retry backoff does not occupy a slot.
+    async fn acquire_permit(&self) -> Option<SemaphorePermit<'_>> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        self.limiter.as_ref()?.acquire().await.ok()
     }
 
-    async fn try_stream_request(&self, url: &str, payload: &serde_json::Value) -> Result<String> {
-        let mut resp = self.client.post(url).json(payload).send().await.map_err(map_err)?;
+    async fn try_once_request(&self, url: &str, payload: &serde_json::Value, call: &CallOptions) -> Result<GrokResponse> {
+        let _permit = self.acquire_permit().await;
+        let timeout = call.timeout.unwrap_or(Duration::from_secs(REQUEST_TIMEOUT));
+        let resp = self.client.post(url)
+            .header(ACCEPT, "application/json")
+            .timeout(timeout)
+            .json(payload)
+            .send()
+            .await
+            .map_err(|e| map_err_after(e, timeout))?;
 
+        let status = resp.status().as_u16();
         if !resp.status().is_success() {
-            let status = resp.status().as_u16();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(GrokError::Api { status, message: body });
+            return Err(error_from_response(resp).await);
         }
 
-        let mut content = String::new();
-        let mut buffer = Vec::new();
-        let mut done = false;
+        let v: serde_json::Value = ensure_json(resp).await?.json().await.map_err(|e| map_err_after(e, timeout))?;
+        let (content, usage) = match self.api_style {
+            ApiStyle::Chat => (
+                v.pointer("/choices/0/message/content").and_then(|c| c.as_str()).map(String::from),
+                parse_usage(&v),
+            ),
+            ApiStyle::Responses => (responses_output_text(&v), parse_responses_usage(&v)),
+        };
+        let mut content = content.ok_or_else(|| GrokError::Api {
+            status,
+            error: ApiError::new(match self.api_style {
+                ApiStyle::Chat => "Response missing choices[0].message.content",
+                ApiStyle::Responses => "Response missing output_text content",
+            }),
+        })?;
 
-        loop {
-            let chunk = tokio::time::timeout(Duration::from_secs(READ_TIMEOUT), resp.chunk())
-                .await
-                .map_err(|_| GrokError::Timeout(READ_TIMEOUT))?
-                .map_err(map_err)?;
+        let partial = content.len() > self.max_content_bytes;
+        if partial {
+            self.truncate_content(&mut content);
+        }
+        Ok(GrokResponse {
+            content,
+            usage,
+            model: parse_model(&v).or_else(|| parse_model(payload)).unwrap_or_default(),
+            continue_token: None,
+            partial,
+        })
+    }
+
+    /// `responded` is notified once the response headers arrive with a success status.
+    async fn try_stream_request(
+        &self,
+        url: &str,
+        payload: &serde_json::Value,
+        call: &CallOptions,
+        responded: Option<&Notify>,
+        forwarded: &AtomicBool,
+    ) -> Result<GrokResponse> {
+        let _permit = self.acquire_permit().await;
+        let timeout = call.timeout.unwrap_or(Duration::from_secs(REQUEST_TIMEOUT));
+        let resp = self.client.post(url)
+            .header(ACCEPT, "text/event-stream")
+            .timeout(timeout)
+            .json(payload)
+            .send()
+            .await
+            .map_err(|e| map_err_after(e, timeout))?;
 
+        if !resp.status().is_success() {
+            return Err(error_from_response(resp).await);
+        }
+        if let Some(responded) = responded {
+            responded.notify_one();
+        }
+
+        let mut received = 0usize;
+        let mut reported = 0usize;
+        let mut reported_at = Instant::now();
+        let on_delta = |delta: &str| {
+            received += delta.len();
+            let Some(tx) = &call.progress else { return };
+            if call.stream_deltas {
+                forwarded.store(true, Ordering::Relaxed);
+                let _ = tx.send(Progress { progress: received as f64, total: None, message: delta.to_string() });
+            } else if received - reported >= PROGRESS_BYTES || reported_at.elapsed() >= PROGRESS_INTERVAL {
+                let _ = tx.send(Progress {
+                    progress: received as f64,
+                    total: None,
+                    message: format!("received {received} bytes"),
+                });
+                reported = received;
+                reported_at = Instant::now();
+            }
+        };
+        let requested_model = parse_model(payload).unwrap_or_default();
+        self.read_stream(resp, timeout, &requested_model, on_delta).await
+    }
+
+    /// Sends one streaming search or fetch request, without retries or cache, and returns the body
+    /// line by line exactly as received: `data:` payloads, keepalive comments, `event:` fields and
+    /// the blank lines that end events. Stops at end of stream or after `max_lines` lines.
+    pub async fn raw_stream(&self, fetch: bool, input: &str, max_lines: usize) -> Result<Vec<String>> {
+        if self.dry_run {
+            return Err(GrokError::ConfigInvalid("raw streams are unavailable with GROK_DRY_RUN".into()));
+        }
+        let messages = if fetch {
+            let prompt = self.fetch_prompt.as_deref().unwrap_or(FETCH_PROMPT);
+            chat_messages(prompt, &format!("{}\n{}", input, FetchFormat::Markdown.instruction()))
+        } else {
+            chat_messages(&self.search_prompt, input)
+        };
+        let model = if fetch { &self.fetch_model } else { &self.search_model };
+        let payload = self.chat_payload(&messages, model, true);
+
+        self.breaker.check()?;
+        let _permit = self.acquire_permit().await;
+        let timeout = Duration::from_secs(REQUEST_TIMEOUT);
+        let mut resp = self.client.post(self.chat_url())
+            .header(ACCEPT, "text/event-stream")
+            .timeout(timeout)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| map_err_after(e, timeout))?;
+        if !resp.status().is_success() {
+            return Err(error_from_response(resp).await);
+        }
+
+        let mut lines = Vec::new();
+        let mut buffer = LineBuffer::default();
+        while lines.len() < max_lines {
+            let chunk = tokio::time::timeout(self.read_timeout, resp.chunk())
+                .await
+                .map_err(|_| GrokError::Timeout(self.read_timeout.as_secs()))?
+                .map_err(|e| map_err_after(e, timeout))?;
             let Some(data) = chunk else { break };
-            buffer.extend_from_slice(&data);
+            buffer.push(&data);
+            while lines.len() < max_lines
+                && let Some(line) = buffer.next_line()
+            {
+                lines.push(line);
+            }
+        }
+        if lines.len() < max_lines {
+            lines.extend(buffer.finish());
+        }
+        Ok(lines)
+    }
+
+    /// Decodes an SSE completion stream in the configured API style, handing each content delta
+    /// to `on_delta` as it arrives.
+    /// `requested_model` is reported when the stream does not name the responding model.
+    async fn read_stream(
+        &self,
+        mut resp: reqwest::Response,
+        timeout: Duration,
+        requested_model: &str,
+        mut on_delta: impl FnMut(&str),
+    ) -> Result<GrokResponse> {
+        let mut content = String::new();
+        let mut usage = None;
+        let mut model = None;
+        let mut buffer = LineBuffer::default();
+        let mut event_data: Option<String> = None;
+        let mut done = false;
+        let mut partial = false;
+        let started = Instant::now();
+        let mut last_content = started;
 
-            while let Some(pos) = buffer.iter().position(|&b| b == b'\n') {
-                let line: Vec<u8> = buffer.drain(..=pos).collect();
-                let line = String::from_utf8_lossy(&line);
-                let line = line.trim();
+        loop {
+            // Keepalive comments and empty deltas keep the connection busy without progress,
+            // so the stall watchdog only resets on actual content.
+            let stall_left = self.stream_stall.map(|stall| stall.saturating_sub(last_content.elapsed()));
+            let mut wait = stall_left.map_or(self.read_timeout, |left| left.min(self.read_timeout));
+            let max_left = self.stream_max.map(|max| max.saturating_sub(started.elapsed()));
+            if let Some(left) = max_left {
+                wait = wait.min(left);
+            }
+            let next = tokio::time::timeout(wait, resp.chunk()).await;
+            // The overall deadline ends the stream early but keeps what has arrived.
+            if next.is_err()
+                && let Some(max) = self.stream_max
+                && started.elapsed() >= max
+            {
+                warn!("Stream exceeded {}s, returning partial content", max.as_secs());
+                content.push_str(&format!("{TRUNCATION_MARKER}stream exceeded {} s]", max.as_secs()));
+                done = true;
+                partial = true;
+                break;
+            }
+            let chunk = next
+                .map_err(|_| match self.stream_stall {
+                    Some(stall) if last_content.elapsed() >= stall => {
+                        warn!("Stream stalled: no content for {}s", stall.as_secs());
+                        GrokError::Timeout(stall.as_secs())
+                    }
+                    _ => GrokError::Timeout(self.read_timeout.as_secs()),
+                })
+                .and_then(|chunk| chunk.map_err(|e| map_err_after(e, timeout)));
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                // With GROK_PARTIAL_ON_TIMEOUT, a timeout keeps the content received so far.
+                Err(GrokError::Timeout(secs)) if self.partial_on_timeout && !content.is_empty() => {
+                    warn!("Stream timed out after {}s, returning {} bytes of partial content", secs, content.len());
+                    content.push_str("\n\n[partial: timed out]");
+                    done = true;
+                    partial = true;
+                    break;
+                }
+                Err(e) => return Err(e),
+            };
 
-                if line.is_empty() || line.starts_with(':') { continue; }
-                if !line.starts_with("data:") { continue; }
+            // An event's data may span several `data:` lines; it is complete at the next blank line.
+            let eof = chunk.is_none();
+            let mut events = Vec::new();
+            match chunk {
+                Some(data) => {
+                    self.metrics.record_stream_bytes(data.len());
+                    buffer.push(&data);
+                    while let Some(line) = buffer.next_line() {
+                        sse_line(&line, &mut event_data, &mut events);
+                    }
+                }
+                // Flush an unterminated last line and an event missing its closing blank line.
+                None => {
+                    if let Some(line) = buffer.finish() {
+                        sse_line(&line, &mut event_data, &mut events);
+                    }
+                    events.extend(event_data.take());
+                }
+            }
 
-                let data = line[5..].trim();
+            for data in events.iter().flat_map(|event| event_payloads(event)) {
+                let data = data.trim();
                 if data == "[DONE]" { done = true; break; }
                 if data.is_empty() { continue; }
 
-                if let Ok(v) = serde_json::from_str::<serde_json::Value>(data) {
-                    if let Some(choices) = v.get("choices").and_then(|c| c.as_array()) {
-                        for choice in choices {
-                            if let Some(text) = choice.get("delta").and_then(|d| d.get("content")).and_then(|c| c.as_str()) {
-                                content.push_str(text);
-                                if content.len() > MAX_CONTENT_BYTES {
-                                    content.truncate(MAX_CONTENT_BYTES);
-                                    warn!("Content exceeded 10MB, truncating");
-                                    return Ok(content);
-                                }
-                            }
-                        }
+                let Ok(v) = serde_json::from_str::<serde_json::Value>(data) else { continue };
+                let event = match self.api_style {
+                    ApiStyle::Chat => chat_stream_event(&v),
+                    ApiStyle::Responses => responses_stream_event(&v)?,
+                };
+                usage = event.usage.or(usage);
+                model = event.model.or(model);
+                done = event.done;
+
+                for text in &event.deltas {
+                    if !text.is_empty() {
+                        last_content = Instant::now();
+                    }
+                    on_delta(text);
+                    content.push_str(text);
+                    if content.len() > self.max_content_bytes {
+                        self.truncate_content(&mut content);
+                        done = true;
+                        partial = true;
+                        break;
                     }
                 }
+                if done { break; }
             }
-            if done { break; }
+            if done || eof { break; }
         }
 
         if !done { warn!("Stream ended without [DONE]"); }
-        Ok(content)
+        Ok(GrokResponse {
+            content,
+            usage,
+            model: model.unwrap_or_else(|| requested_model.to_string()),
+            continue_token: None,
+            partial,
+        })
     }
 
+    /// Cuts content down to the configured limit and appends a marker so consumers know it was cut.
+    fn truncate_content(&self, content: &mut String) {
+        let mb = self.max_content_bytes / (1024 * 1024);
+        warn!("Content exceeded {}MB, truncating", mb);
+        truncate_at_char_boundary(content, self.max_content_bytes);
+        content.push_str(&format!("{TRUNCATION_MARKER}exceeded {mb} MB]"));
+    }
+
+    /// Delay before retry number `attempt + 1`, following the AWS "Exponential Backoff And Jitter" strategies.
+    ///
+    /// With `cap = min(retry_max_wait, retry_multiplier ^ attempt)` seconds:
+    /// - `none`:  `sleep = cap`
+    /// - `proportional` (default): `sleep = cap * random(0.9, 1.1)`
+    /// - `equal`: `sleep = cap / 2 + random(0, cap / 2)`
+    /// - `full`:  `sleep = random(0, cap)`
+    ///
+    /// The result is never shorter than 100 ms.
     fn backoff(&self, attempt: u32) -> Duration {
-        let base = 1.0_f64 * self.retry_multiplier.powi(attempt as i32);
-        let capped = base.min(self.retry_max_wait as f64);
-        let jitter = rand::thread_rng().gen_range(0.9..=1.1);
-        Duration::from_secs_f64((capped * jitter).max(0.1))
+        let cap = self.retry_multiplier.powi(attempt as i32).min(self.retry_max_wait as f64);
+        let mut rng = rand::thread_rng();
+        let secs = match self.retry_jitter {
+            RetryJitter::None => cap,
+            RetryJitter::Proportional => cap * rng.gen_range(0.9..=1.1),
+            RetryJitter::Equal => cap / 2.0 + rng.gen_range(0.0..=cap / 2.0),
+            RetryJitter::Full => rng.gen_range(0.0..=cap),
+        };
+        Duration::from_secs_f64(secs.max(0.1))
     }
 }
 
+fn chat_messages(system_prompt: &str, user_content: &str) -> Vec<serde_json::Value> {
+    vec![
+        serde_json::json!({ "role": "system", "content": system_prompt }),
+        serde_json::json!({ "role": "user", "content": user_content }),
+    ]
+}
+
+/// Like `chat_messages`, with the caller's earlier turns between the system prompt and the query.
+fn search_messages(system_prompt: &str, context: &[(String, String)], user_content: &str) -> Vec<serde_json::Value> {
+    let mut messages = vec![serde_json::json!({ "role": "system", "content": system_prompt })];
+    messages.extend(context.iter().map(|(role, content)| serde_json::json!({ "role": role, "content": content })));
+    messages.push(serde_json::json!({ "role": "user", "content": user_content }));
+    messages
+}
+
+/// Checks that `content` parses as JSON, tolerating a surrounding ```json code fence,
+/// and returns the bare JSON text.
+fn normalize_json(content: &str) -> Result<String> {
+    let unfenced = unfence_json(content);
+    serde_json::from_str::<serde_json::Value>(unfenced)?;
+    Ok(unfenced.to_string())
+}
+
+fn unfence_json(content: &str) -> &str {
+    let trimmed = content.trim();
+    trimmed.strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(|inner| inner.trim_start_matches("json").trim())
+        .unwrap_or(trimmed)
+}
+
+/// Drops repeated results from a JSON array of objects, keyed by their `url` (or `link`) field
+/// and keeping the first occurrence. An `include_sources` object has its `results` and `sources`
+/// arrays deduplicated separately. Returns `None` when the content is neither shape or has no
+/// duplicates, so the model output is left byte-for-byte intact.
+fn dedupe_results(content: &str) -> Option<String> {
+    let mut value: serde_json::Value = serde_json::from_str(unfence_json(content)).ok()?;
+    let removed = match &mut value {
+        serde_json::Value::Array(items) => dedupe_by_url(items),
+        serde_json::Value::Object(obj) if obj.get("results").is_some_and(serde_json::Value::is_array) => obj
+            .iter_mut()
+            .filter(|(key, _)| matches!(key.as_str(), "results" | "sources"))
+            .filter_map(|(_, items)| items.as_array_mut())
+            .map(dedupe_by_url)
+            .sum(),
+        _ => return None,
+    };
+    if removed == 0 {
+        return None;
+    }
+    debug!("Removed {} duplicate search results", removed);
+    serde_json::to_string_pretty(&value).ok()
+}
+
+/// Removes objects whose `url`/`link` repeats an earlier one, returning how many were removed.
+fn dedupe_by_url(items: &mut Vec<serde_json::Value>) -> usize {
+    let mut seen = std::collections::HashSet::new();
+    let total = items.len();
+    items.retain(|item| {
+        let url = item.get("url").or_else(|| item.get("link")).and_then(|u| u.as_str());
+        match url {
+            Some(url) => seen.insert(url.trim().trim_end_matches('/').to_string()),
+            None => true,
+        }
+    });
+    total - items.len()
+}
+
+/// Whether a search response holds no results: blank, or an empty JSON array (fenced or not),
+/// alone or as the `results` of an `include_sources` object.
+fn is_empty_result(content: &str) -> bool {
+    let unfenced = unfence_json(content);
+    if unfenced.is_empty() {
+        return true;
+    }
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(unfenced) else { return false };
+    let results = if value.is_object() { value.get("results") } else { Some(&value) };
+    matches!(results, Some(serde_json::Value::Array(items)) if items.is_empty())
+}
+
+fn sum_usage(a: Option<Usage>, b: Option<Usage>) -> Option<Usage> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(Usage {
+            prompt_tokens: a.prompt_tokens + b.prompt_tokens,
+            completion_tokens: a.completion_tokens + b.completion_tokens,
+            total_tokens: a.total_tokens + b.total_tokens,
+        }),
+        (a, b) => a.or(b),
+    }
+}
+
+fn log_request(kind: &str, input: &str, resp: &GrokResponse, elapsed: Duration) {
+    let (prompt, completion, total) = resp.usage.as_ref()
+        .map(|u| (u.prompt_tokens, u.completion_tokens, u.total_tokens))
+        .unwrap_or_default();
+    info!(
+        "{} completed: input={:?} model={} latency_ms={} prompt_tokens={} completion_tokens={} total_tokens={}",
+        kind, input, resp.model, elapsed.as_millis(), prompt, completion, total
+    );
+}
+
+/// Rejects a successful response whose Content-Type is not JSON, such as a proxy's HTML login
+/// page, quoting the start of the body. A missing Content-Type is left to the JSON parser.
+async fn ensure_json(resp: reqwest::Response) -> Result<reqwest::Response> {
+    let content_type = resp.headers().get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    if content_type.is_empty() || content_type.to_lowercase().contains("json") {
+        return Ok(resp);
+    }
+    let body = resp.text().await.unwrap_or_default();
+    Err(GrokError::NotJson { content_type, preview: body.trim().chars().take(200).collect() })
+}
+
+fn search_cache_key(query: &str, opts: &SearchOptions) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    query.hash(&mut hasher);
+    opts.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn fetch_cache_key(url: &str, opts: &FetchOptions) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    normalize_url(url).hash(&mut hasher);
+    opts.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Canonical form of a URL for cache lookups: lowercase scheme and host (done by the parser),
+/// no fragment, and query parameters sorted. Unparseable input is only trimmed.
+fn normalize_url(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url.trim()) else {
+        return url.trim().to_string();
+    };
+    parsed.set_fragment(None);
+    let mut pairs: Vec<(String, String)> = parsed.query_pairs().into_owned().collect();
+    if pairs.is_empty() {
+        parsed.set_query(None);
+    } else {
+        pairs.sort();
+        parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    parsed.to_string()
+}
+
+/// Looks up a cached response, evicting expired entries along the way. A zero TTL disables the cache.
+fn cache_lookup(cache: &Mutex<HashMap<u64, (Instant, GrokResponse)>>, ttl: Duration, key: u64) -> Option<GrokResponse> {
+    if ttl.is_zero() {
+        return None;
+    }
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    cache.retain(|_, (stored_at, _)| stored_at.elapsed() < ttl);
+    cache.get(&key).map(|(_, resp)| resp.clone())
+}
+
+/// Partial results are not stored, so a cut-off answer is not replayed for the whole TTL; nor are
+/// results with a `continue_token`, which may expire before the cache entry does.
+fn cache_store(cache: &Mutex<HashMap<u64, (Instant, GrokResponse)>>, ttl: Duration, key: u64, resp: &GrokResponse) {
+    if ttl.is_zero() || resp.partial || resp.continue_token.is_some() {
+        return;
+    }
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    cache.insert(key, (Instant::now(), resp.clone()));
+}
+
+/// Splits a byte stream into lines. Bytes are buffered until a full line has arrived and only then
+/// decoded, so a multibyte UTF-8 character split across chunks is reassembled rather than turned
+/// into replacement characters: `\n` never occurs inside a multibyte sequence.
+#[derive(Default)]
+struct LineBuffer {
+    bytes: Vec<u8>,
+}
+
+impl LineBuffer {
+    fn push(&mut self, chunk: &[u8]) {
+        self.bytes.extend_from_slice(chunk);
+    }
+
+    /// The next complete line, without its `\n` or `\r\n` terminator.
+    fn next_line(&mut self) -> Option<String> {
+        let pos = self.bytes.iter().position(|&b| b == b'\n')?;
+        let line: Vec<u8> = self.bytes.drain(..=pos).collect();
+        Some(decode_line(&line))
+    }
+
+    /// Whatever follows the last terminator once the stream has ended.
+    fn finish(&mut self) -> Option<String> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        let line = std::mem::take(&mut self.bytes);
+        Some(decode_line(&line))
+    }
+}
+
+/// A complete line is valid UTF-8 from any well-behaved server; lossy decoding only
+/// replaces bytes that were invalid to begin with.
+fn decode_line(line: &[u8]) -> String {
+    String::from_utf8_lossy(line).trim_end_matches(['\r', '\n']).to_string()
+}
+
+/// Feeds one SSE line into the event being assembled: `data:` values accumulate, joined by
+/// newlines, and a blank line completes the event. Comments and other fields carry no content.
+/// Data that already forms a complete payload is dispatched at once, so streams without blank
+/// separators still deliver deltas and `[DONE]` as they arrive; only a split JSON document waits.
+fn sse_line(line: &str, event_data: &mut Option<String>, events: &mut Vec<String>) {
+    if line.is_empty() {
+        events.extend(event_data.take());
+        return;
+    }
+    let Some(value) = line.strip_prefix("data:") else { return };
+    let value = value.strip_prefix(' ').unwrap_or(value);
+    match event_data.take() {
+        None if is_complete_payload(value) => events.push(value.to_string()),
+        None => *event_data = Some(value.to_string()),
+        Some(mut data) => {
+            data.push('\n');
+            data.push_str(value);
+            if is_complete_payload(&data) {
+                events.push(data);
+            } else if is_complete_payload(value) {
+                // The pending fragment never completed; give it up rather than let it swallow the stream.
+                data.truncate(data.len() - value.len() - 1);
+                events.push(data);
+                events.push(value.to_string());
+            } else {
+                *event_data = Some(data);
+            }
+        }
+    }
+}
+
+fn is_complete_payload(data: &str) -> bool {
+    let data = data.trim();
+    data == "[DONE]" || serde_json::from_str::<serde::de::IgnoredAny>(data).is_ok()
+}
+
+/// The payloads of one SSE event. Normally its data is a single JSON document, even when split
+/// over several lines; data that does not parse as a whole falls back to one payload per line.
+fn event_payloads(data: &str) -> Vec<&str> {
+    if !data.contains('\n') || serde_json::from_str::<serde::de::IgnoredAny>(data).is_ok() {
+        return vec![data];
+    }
+    data.lines().collect()
+}
+
+/// What a single SSE `data:` payload contributes to the response.
+#[derive(Default)]
+struct StreamEvent {
+    deltas: Vec<String>,
+    usage: Option<Usage>,
+    model: Option<String>,
+    done: bool,
+}
+
+/// `/chat/completions` chunks: text in `choices[].delta.content`, usage on the final chunk,
+/// and the stream ends with a `[DONE]` sentinel handled by the caller.
+fn chat_stream_event(v: &serde_json::Value) -> StreamEvent {
+    let deltas = v.get("choices")
+        .and_then(|c| c.as_array())
+        .map(|choices| choices.iter()
+            .filter_map(|choice| choice.pointer("/delta/content").and_then(|c| c.as_str()))
+            .map(String::from)
+            .collect())
+        .unwrap_or_default();
+    StreamEvent { deltas, usage: parse_usage(v), model: parse_model(v), done: false }
+}
+
+/// `/responses` events are typed: text arrives in `response.output_text.delta`, and
+/// `response.completed` carries the final usage and ends the stream.
+fn responses_stream_event(v: &serde_json::Value) -> Result<StreamEvent> {
+    let mut event = StreamEvent::default();
+    match v.get("type").and_then(|t| t.as_str()).unwrap_or_default() {
+        "response.output_text.delta" => {
+            if let Some(delta) = v.get("delta").and_then(|d| d.as_str()) {
+                event.deltas.push(delta.to_string());
+            }
+        }
+        "response.completed" => {
+            if let Some(response) = v.get("response") {
+                event.usage = parse_responses_usage(response);
+                event.model = parse_model(response);
+            }
+            event.done = true;
+        }
+        "response.failed" | "error" => {
+            let body = v.get("response").and_then(|r| r.get("error")).unwrap_or(v);
+            return Err(GrokError::StreamError(ApiError::from_body(&serde_json::json!({ "error": body }).to_string())));
+        }
+        _ => {}
+    }
+    Ok(event)
+}
+
+/// Concatenates the `output_text` parts of a non-streaming `/responses` result.
+fn responses_output_text(v: &serde_json::Value) -> Option<String> {
+    let output = v.get("output")?.as_array()?;
+    let parts: Vec<&str> = output.iter()
+        .filter_map(|item| item.get("content").and_then(|c| c.as_array()))
+        .flatten()
+        .filter(|part| part.get("type").and_then(|t| t.as_str()) == Some("output_text"))
+        .filter_map(|part| part.get("text").and_then(|t| t.as_str()))
+        .collect();
+    if parts.is_empty() { None } else { Some(parts.concat()) }
+}
+
+/// `/responses` reports `input_tokens`/`output_tokens`; map them onto the chat-style fields.
+fn parse_responses_usage(v: &serde_json::Value) -> Option<Usage> {
+    let usage = v.get("usage").filter(|u| u.is_object())?;
+    let field = |name: &str| usage.get(name).and_then(|n| n.as_u64()).unwrap_or(0);
+    Some(Usage {
+        prompt_tokens: field("input_tokens"),
+        completion_tokens: field("output_tokens"),
+        total_tokens: field("total_tokens"),
+    })
+}
+
+fn parse_usage(v: &serde_json::Value) -> Option<Usage> {
+    v.get("usage").filter(|u| u.is_object()).and_then(|u| serde_json::from_value(u.clone()).ok())
+}
+
+fn parse_model(v: &serde_json::Value) -> Option<String> {
+    v.get("model").and_then(|m| m.as_str()).filter(|m| !m.is_empty()).map(String::from)
+}
+
+/// Caps `content` at `max_chars` characters, marking the cut.
+fn truncate_chars(content: &mut String, max_chars: usize) {
+    if let Some((idx, _)) = content.char_indices().nth(max_chars) {
+        content.truncate(idx);
+        content.push_str(&format!("{TRUNCATION_MARKER}exceeded {max_chars} characters]"));
+    }
+}
+
+/// The last `CONTINUATION_TAIL_CHARS` characters before a trailing truncation note,
+/// or `None` when the content was not cut off.
+fn truncated_tail(content: &str) -> Option<String> {
+    let idx = content.rfind(TRUNCATION_MARKER)?;
+    if content[idx + TRUNCATION_MARKER.len()..].contains('\n') {
+        return None;
+    }
+    let delivered = content[..idx].trim_end();
+    let skip = delivered.chars().count().saturating_sub(CONTINUATION_TAIL_CHARS);
+    Some(delivered.chars().skip(skip).collect())
+}
+
+fn truncate_at_char_boundary(s: &mut String, max_bytes: usize) {
+    let mut end = max_bytes.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    s.truncate(end);
+}
+
+/// Converts a non-success response into an error. 429 and 503 become `RateLimited`
+/// carrying the server's `Retry-After` hint when present.
+async fn error_from_response(resp: reqwest::Response) -> GrokError {
+    let status = resp.status().as_u16();
+    if matches!(status, 429 | 503) {
+        let retry_after = resp.headers().get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_retry_after);
+        return GrokError::RateLimited { status, retry_after };
+    }
+    let body = resp.text().await.unwrap_or_default();
+    GrokError::Api { status, error: ApiError::from_body(&body) }
+}
+
+/// Parses `Retry-After` as either delay-seconds or an HTTP-date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
 fn map_err(e: reqwest::Error) -> GrokError {
-    if e.is_timeout() { GrokError::Timeout(REQUEST_TIMEOUT) } else { GrokError::Http(e) }
+    map_err_after(e, Duration::from_secs(REQUEST_TIMEOUT))
+}
+
+/// Like `map_err`, but reports the timeout that was actually in effect for the request.
+fn map_err_after(e: reqwest::Error, timeout: Duration) -> GrokError {
+    if e.is_timeout() { GrokError::Timeout(timeout.as_secs()) } else { GrokError::Http(e) }
 }
 
-fn is_retryable(e: &GrokError) -> bool {
+/// Whether `e` falls under one of the `GROK_RETRY_ON` conditions.
+fn is_retryable(e: &GrokError, retry_on: &[RetryOn]) -> bool {
     match e {
-        GrokError::Timeout(_) => true,
-        GrokError::Http(e) => e.is_timeout() || e.is_connect(),
-        GrokError::Api { status, .. } => matches!(status, 429 | 500 | 502 | 503 | 504),
+        GrokError::Timeout(_) => retry_on.contains(&RetryOn::Timeout),
+        GrokError::Http(e) if e.is_timeout() => retry_on.contains(&RetryOn::Timeout),
+        GrokError::Http(e) if e.is_connect() => retry_on.contains(&RetryOn::Connect),
+        GrokError::RateLimited { status, .. } | GrokError::Api { status, .. } => retry_on.iter().any(|c| c.matches_status(*status)),
         _ => false,
     }
 }
 
-fn classify_status(code: u16) -> String {
-    match code {
-        401 | 403 => "AUTH_ERROR",
-        404 => "NOT_FOUND",
-        429 => "RATE_LIMIT",
-        500..=599 => "SERVER_ERROR",
-        _ => "HTTP_ERROR",
-    }.into()
+/// Errors that suggest the backend itself is down, as opposed to a bad request or rate limiting.
+fn is_outage(e: &GrokError) -> bool {
+    match e {
+        GrokError::Timeout(_) => true,
+        GrokError::Http(e) => e.is_timeout() || e.is_connect(),
+        // 503 arrives as `RateLimited`, but is the backend being unavailable all the same.
+        GrokError::Api { status, .. } | GrokError::RateLimited { status, .. } => (500..=599).contains(status),
+        _ => false,
+    }
 }
 
-fn needs_time_context(query: &str) -> bool {
+/// `extra` holds additional lowercase keywords from `GROK_TIME_KEYWORDS`.
+fn needs_time_context(query: &str, extra: &[String]) -> bool {
     let cn = ["今天", "昨天", "明天", "现在", "最新", "最近", "本周", "本月", "今年"];
     for kw in cn { if query.contains(kw) { return true; } }
 
     let lower = query.to_lowercase();
     let en = ["today", "yesterday", "tomorrow", "now", "latest", "recent", "current", "this week", "this month", "this year"];
     for kw in en { if lower.contains(kw) { return true; } }
+    for kw in extra { if lower.contains(kw.as_str()) { return true; } }
 
     // Check for years 2020-2099
     for word in query.split(|c: char| !c.is_ascii_digit()) {
-        if word.len() == 4 {
-            if let Ok(year) = word.parse::<u16>() {
-                if (2020..=2099).contains(&year) { return true; }
-            }
+        if word.len() == 4
+            && let Ok(year) = word.parse::<u16>()
+            && (2020..=2099).contains(&year)
+        {
+            return true;
         }
     }
     false
 }
 
-fn time_context() -> String {
-    let now = Local::now();
-    let offset = now.offset().local_minus_utc();
+fn date_range_constraint(start: Option<NaiveDate>, end: Option<NaiveDate>) -> Option<String> {
+    let fmt = |d: NaiveDate| d.format("%Y-%m-%d").to_string();
+    match (start, end) {
+        (Some(s), Some(e)) => Some(format!("Only include results published between {} and {} (inclusive).", fmt(s), fmt(e))),
+        (Some(s), None) => Some(format!("Only include results published on or after {}.", fmt(s))),
+        (None, Some(e)) => Some(format!("Only include results published on or before {}.", fmt(e))),
+        (None, None) => None,
+    }
+}
+
+/// Formats the current time in `tz` (from `GROK_TIMEZONE`), or the machine's local timezone,
+/// with the `GROK_TIME_FORMAT` string when set.
+fn time_context(tz: Option<Tz>, format: Option<&str>) -> String {
+    match tz {
+        Some(tz) => format_time_context(Utc::now().with_timezone(&tz), Some(tz.name()), format),
+        None => format_time_context(Local::now(), None, format),
+    }
+}
+
+fn format_time_context<Z: TimeZone>(now: DateTime<Z>, zone_name: Option<&str>, format: Option<&str>) -> String
+where
+    Z::Offset: std::fmt::Display,
+{
+    // A custom format carries its own offset (e.g. `%+`); it was validated in Config::load.
+    if let Some(format) = format {
+        return format!("Current time: {}\n", now.format(format));
+    }
+    let offset = now.offset().fix().local_minus_utc();
     let sign = if offset >= 0 { '+' } else { '-' };
     let hours = offset.unsigned_abs() / 3600;
-    format!("Current time: {} (UTC{}{})\n", now.format("%Y-%m-%d %H:%M:%S"), sign, hours)
+    let minutes = offset.unsigned_abs() % 3600 / 60;
+    let utc = if minutes == 0 { format!("UTC{sign}{hours}") } else { format!("UTC{sign}{hours}:{minutes:02}") };
+    let zone = zone_name.map(|name| format!("{name}, {utc}")).unwrap_or(utc);
+    format!("Current time: {} ({zone})\n", now.format("%Y-%m-%d %H:%M:%S"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_buffer_reassembles_multibyte_char_split_across_chunks() {
+        let bytes = "data: 中文\n".as_bytes();
+        // "中" is E4 B8 AD; split after its first byte.
+        let split = "data: ".len() + 1;
+        let mut buffer = LineBuffer::default();
+        buffer.push(&bytes[..split]);
+        assert_eq!(buffer.next_line(), None);
+        buffer.push(&bytes[split..]);
+        let line = buffer.next_line().unwrap();
+        assert_eq!(line, "data: 中文");
+        assert!(!line.contains('\u{FFFD}'));
+        assert_eq!(buffer.next_line(), None);
+        assert_eq!(buffer.finish(), None);
+    }
+
+    #[test]
+    fn line_buffer_finish_returns_unterminated_tail() {
+        let bytes = "data: a\r\ndata: 中".as_bytes();
+        let mut buffer = LineBuffer::default();
+        buffer.push(&bytes[..bytes.len() - 2]);
+        buffer.push(&bytes[bytes.len() - 2..]);
+        assert_eq!(buffer.next_line().as_deref(), Some("data: a"));
+        assert_eq!(buffer.next_line(), None);
+        assert_eq!(buffer.finish().as_deref(), Some("data: 中"));
+        assert_eq!(buffer.finish(), None);
+    }
+
+    fn feed(lines: &[&str]) -> (Vec<Vec<String>>, Option<String>) {
+        let mut event_data = None;
+        let per_line = lines.iter().map(|line| {
+            let mut events = Vec::new();
+            sse_line(line, &mut event_data, &mut events);
+            events
+        }).collect();
+        (per_line, event_data)
+    }
+
+    #[test]
+    fn sse_line_joins_an_event_split_over_data_lines() {
+        let (per_line, pending) = feed(&[r#"data: {"choices":[{"delta":"#, r#"data: {"content":"x"}}]}"#, ""]);
+        assert!(per_line[0].is_empty());
+        assert_eq!(per_line[1].len(), 1);
+        assert!(per_line[2].is_empty());
+        assert_eq!(pending, None);
+        let payloads = event_payloads(&per_line[1][0]);
+        assert_eq!(payloads.len(), 1);
+        let v: serde_json::Value = serde_json::from_str(payloads[0]).unwrap();
+        assert_eq!(chat_stream_event(&v).deltas, vec!["x".to_string()]);
+    }
+
+    #[test]
+    fn sse_line_dispatches_complete_lines_without_blank_separators() {
+        let (per_line, pending) = feed(&[
+            r#"data: {"choices":[{"delta":{"content":"a"}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"b"}}]}"#,
+            "data: [DONE]",
+        ]);
+        assert_eq!(per_line.iter().map(Vec::len).collect::<Vec<_>>(), vec![1, 1, 1]);
+        assert_eq!(per_line[2][0], "[DONE]");
+        assert_eq!(pending, None);
+    }
+
+    #[test]
+    fn responses_failed_event_is_not_retried_or_an_outage() {
+        let event = serde_json::json!({
+            "type": "response.failed",
+            "response": { "error": { "code": "invalid_prompt", "message": "Prompt rejected" } },
+        });
+        let Err(e) = responses_stream_event(&event) else { panic!("expected an error") };
+        assert_eq!(e.code(), "STREAM_ERROR");
+        assert!(e.to_string().contains("Prompt rejected [invalid_prompt]"));
+        assert!(!is_retryable(&e, &RetryOn::DEFAULT));
+        assert!(!is_outage(&e));
+    }
+
+    #[test]
+    fn server_errors_count_as_outages() {
+        assert!(is_outage(&GrokError::RateLimited { status: 503, retry_after: None }));
+        assert!(is_outage(&GrokError::Api { status: 502, error: ApiError::new("bad gateway") }));
+        assert!(!is_outage(&GrokError::RateLimited { status: 429, retry_after: None }));
+        assert!(!is_outage(&GrokError::Api { status: 401, error: ApiError::new("unauthorized") }));
+    }
+
+    #[test]
+    fn dedupe_results_handles_results_with_sources() {
+        let content = r#"{"results":[{"url":"http://a"},{"url":"http://a/"},{"url":"http://b"}],
+            "sources":[{"title":"A","url":"http://a"},{"title":"A again","url":"http://a"}]}"#;
+        let deduped: serde_json::Value = serde_json::from_str(&dedupe_results(content).unwrap()).unwrap();
+        assert_eq!(deduped["results"].as_array().unwrap().len(), 2);
+        assert_eq!(deduped["sources"].as_array().unwrap().len(), 1);
+        assert_eq!(dedupe_results(r#"{"results":[{"url":"http://a"}],"sources":[]}"#), None);
+    }
+
+    #[test]
+    fn is_empty_result_recognises_empty_results_object() {
+        assert!(is_empty_result("[]"));
+        assert!(is_empty_result(r#"{"results":[],"sources":[]}"#));
+        assert!(!is_empty_result(r#"{"results":[{"url":"http://a"}],"sources":[]}"#));
+        assert!(!is_empty_result(r#"{"answer":"none"}"#));
+    }
+
+    #[test]
+    fn sse_line_drops_a_fragment_that_never_completes() {
+        let (per_line, pending) = feed(&[r#"data: {"choices":"#, r#"data: {"choices":[]}"#]);
+        assert_eq!(per_line[1], vec![r#"{"choices":"#.to_string(), r#"{"choices":[]}"#.to_string()]);
+        assert_eq!(pending, None);
+    }
 }
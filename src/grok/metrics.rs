@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the request duration histogram buckets.
+const DURATION_BUCKETS: [f64; 9] = [0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+/// Process-wide request counters, rendered in the Prometheus text exposition format.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    hedges: AtomicU64,
+    stream_bytes: AtomicU64,
+    failures: Mutex<BTreeMap<&'static str, u64>>,
+    duration_buckets: [AtomicU64; DURATION_BUCKETS.len()],
+    duration_count: AtomicU64,
+    duration_sum_micros: AtomicU64,
+}
+
+impl Metrics {
+    /// Records one API attempt and how long it took.
+    pub fn record_request(&self, elapsed: Duration) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let secs = elapsed.as_secs_f64();
+        for (bound, bucket) in DURATION_BUCKETS.iter().zip(&self.duration_buckets) {
+            if secs <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.duration_count.fetch_add(1, Ordering::Relaxed);
+        self.duration_sum_micros.fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_hedge(&self) {
+        self.hedges.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_failure(&self, kind: &'static str) {
+        *self.failures.lock().unwrap_or_else(|e| e.into_inner()).entry(kind).or_default() += 1;
+    }
+
+    pub fn record_stream_bytes(&self, bytes: usize) {
+        self.stream_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counter = |out: &mut String, name: &str, help: &str, value: u64| {
+            let _ = writeln!(out, "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}");
+        };
+        counter(&mut out, "grok_requests_total", "API request attempts, including retries.", self.requests.load(Ordering::Relaxed));
+        counter(&mut out, "grok_retries_total", "API request attempts that were retried.", self.retries.load(Ordering::Relaxed));
+        counter(&mut out, "grok_hedged_requests_total", "Second requests sent because the first was slow (GROK_HEDGE_DELAY_MS).", self.hedges.load(Ordering::Relaxed));
+        counter(&mut out, "grok_stream_bytes_total", "Bytes received from streaming responses.", self.stream_bytes.load(Ordering::Relaxed));
+
+        let _ = writeln!(out, "# HELP grok_failures_total Failed API request attempts by error kind.\n# TYPE grok_failures_total counter");
+        for (kind, count) in self.failures.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            let _ = writeln!(out, "grok_failures_total{{error=\"{kind}\"}} {count}");
+        }
+
+        let _ = writeln!(out, "# HELP grok_request_duration_seconds API request attempt duration.\n# TYPE grok_request_duration_seconds histogram");
+        for (bound, bucket) in DURATION_BUCKETS.iter().zip(&self.duration_buckets) {
+            let _ = writeln!(out, "grok_request_duration_seconds_bucket{{le=\"{bound}\"}} {}", bucket.load(Ordering::Relaxed));
+        }
+        let count = self.duration_count.load(Ordering::Relaxed);
+        let sum = self.duration_sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "grok_request_duration_seconds_bucket{{le=\"+Inf\"}} {count}");
+        let _ = writeln!(out, "grok_request_duration_seconds_sum {sum}");
+        let _ = writeln!(out, "grok_request_duration_seconds_count {count}");
+        out
+    }
+}
@@ -1,4 +1,11 @@
+mod circuit;
 mod client;
+mod metrics;
 pub mod prompts;
+mod ratelimit;
+mod results;
+mod robots;
 
-pub use client::GrokClient;
+pub use client::{
+    CallOptions, FetchFormat, FetchMode, FetchOptions, GrokClient, GrokResponse, Progress, ProgressSender, SafeSearch, SearchOptions, SortOrder, REQUEST_TIMEOUT,
+};
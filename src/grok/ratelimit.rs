@@ -0,0 +1,69 @@
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Snapshot of the limiter for `health_check`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitStatus {
+    pub max_qps: f64,
+    pub available_tokens: f64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket that bounds outbound requests to `rate` per second (`GROK_MAX_QPS`). The bucket
+/// holds at most one second's worth of tokens (at least one), so idle time allows only a short burst.
+/// State is shared across clones.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    rate: f64,
+    capacity: f64,
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64) -> Self {
+        let capacity = rate.max(1.0);
+        Self {
+            rate,
+            capacity,
+            bucket: Arc::new(Mutex::new(Bucket { tokens: capacity, updated: Instant::now() })),
+        }
+    }
+
+    /// Waits until a token is available and takes it.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+                self.refill(&mut bucket);
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub fn status(&self) -> RateLimitStatus {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+        self.refill(&mut bucket);
+        RateLimitStatus {
+            max_qps: self.rate,
+            available_tokens: (bucket.tokens * 100.0).floor() / 100.0,
+        }
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let earned = now.duration_since(bucket.updated).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + earned).min(self.capacity);
+        bucket.updated = now;
+    }
+}
@@ -0,0 +1,137 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+use crate::error::Result;
+
+/// One search hit in the structured output of a `strict_json` search.
+/// Every field is optional: anything the model left out or got the wrong type for is `None`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SearchResult {
+    /// Result title
+    pub title: Option<String>,
+    /// Link to the result
+    pub url: Option<String>,
+    /// Short description of the content
+    pub snippet: Option<String>,
+    /// Publication date (ISO 8601), when known
+    pub published_date: Option<String>,
+    /// Site or publisher name
+    pub source: Option<String>,
+}
+
+/// One entry of the `sources` bibliography returned with `include_sources`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SearchSource {
+    /// Source title
+    pub title: Option<String>,
+    /// Link to the source
+    pub url: Option<String>,
+}
+
+/// Output shape of a `strict_json` search with `include_sources`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SearchResultsWithSources {
+    /// Search results
+    pub results: Vec<SearchResult>,
+    /// Every source consulted for the answer, including those mentioned only inline
+    pub sources: Vec<SearchSource>,
+}
+
+/// The output contract appended to the search system prompt for `strict_json` searches.
+pub static RESULT_SCHEMA_PROMPT: LazyLock<String> = LazyLock::new(|| {
+    let schema = schemars::schema_for!(Vec<SearchResult>);
+    let schema = serde_json::to_string_pretty(&schema).unwrap_or_default();
+    format!(
+        "\n\n## Output Schema\n本次输出必须是符合以下JSON Schema的数组（优先于上文的字段定义）；未知的字段使用null：\n```json\n{schema}\n```"
+    )
+});
+
+/// The output contract for `strict_json` searches with `include_sources`, replacing `RESULT_SCHEMA_PROMPT`.
+pub static SOURCES_SCHEMA_PROMPT: LazyLock<String> = LazyLock::new(|| {
+    let schema = schemars::schema_for!(SearchResultsWithSources);
+    let schema = serde_json::to_string_pretty(&schema).unwrap_or_default();
+    format!(
+        "\n\n## Output Schema\n本次输出必须是符合以下JSON Schema的对象（优先于上文的字段定义），包含`results`与`sources`两个数组；未知的字段使用null：\n```json\n{schema}\n```"
+    )
+});
+
+impl SearchResult {
+    /// Reads one result object, accepting the field names of the default prompt
+    /// (`description`, `published_at`, `link`) as fallbacks.
+    fn from_value(item: &serde_json::Value) -> Option<Self> {
+        let item = item.as_object()?;
+        let field = |names: &[&str]| {
+            names.iter()
+                .filter_map(|name| item.get(*name)?.as_str())
+                .map(str::trim)
+                .find(|s| !s.is_empty())
+                .map(String::from)
+        };
+        Some(Self {
+            title: field(&["title"]),
+            url: field(&["url", "link"]),
+            snippet: field(&["snippet", "description"]),
+            published_date: field(&["published_date", "published_at"]),
+            source: field(&["source"]),
+        })
+    }
+}
+
+impl SearchSource {
+    /// Reads one source object; entries without a URL are dropped.
+    fn from_value(item: &serde_json::Value) -> Option<Self> {
+        let result = SearchResult::from_value(item)?;
+        result.url.as_ref()?;
+        Some(Self { title: result.title, url: result.url })
+    }
+}
+
+/// Parses model output that already passed JSON validation into the `SearchResult` shape
+/// and re-serializes it. Accepts a bare array or an object with a `results` array;
+/// non-object entries are dropped.
+pub fn structure_results(content: &str) -> Result<String> {
+    let value: serde_json::Value = serde_json::from_str(content)?;
+    let items = match &value {
+        serde_json::Value::Array(items) => items,
+        serde_json::Value::Object(obj) => match obj.get("results") {
+            Some(serde_json::Value::Array(items)) => items,
+            _ => return Err(not_a_result_list()),
+        },
+        _ => return Err(not_a_result_list()),
+    };
+    let results: Vec<SearchResult> = items.iter().filter_map(SearchResult::from_value).collect();
+    Ok(serde_json::to_string_pretty(&results)?)
+}
+
+/// Checks that model output holds both a `results` and a `sources` array, as `include_sources`
+/// requires under `strict_json`.
+pub fn check_sources(content: &str) -> Result<()> {
+    let value: serde_json::Value = serde_json::from_str(content)?;
+    for key in ["results", "sources"] {
+        if !value.get(key).is_some_and(serde_json::Value::is_array) {
+            return Err(json_error(&format!("expected a JSON object with a `{key}` array")));
+        }
+    }
+    Ok(())
+}
+
+/// Like `structure_results`, for output that passed `check_sources`: normalizes both arrays
+/// and re-serializes them as a `SearchResultsWithSources` object.
+pub fn structure_results_with_sources(content: &str) -> Result<String> {
+    let value: serde_json::Value = serde_json::from_str(content)?;
+    let items = |key: &str| value.get(key).and_then(serde_json::Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    let structured = SearchResultsWithSources {
+        results: items("results").iter().filter_map(SearchResult::from_value).collect(),
+        sources: items("sources").iter().filter_map(SearchSource::from_value).collect(),
+    };
+    Ok(serde_json::to_string_pretty(&structured)?)
+}
+
+fn not_a_result_list() -> crate::error::GrokError {
+    json_error("expected a JSON array of search results")
+}
+
+fn json_error(msg: &str) -> crate::error::GrokError {
+    <serde_json::Error as serde::de::Error>::custom(msg).into()
+}
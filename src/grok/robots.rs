@@ -0,0 +1,163 @@
+use reqwest::Url;
+
+/// Product token matched against `User-agent` lines. Parsing follows RFC 9309.
+pub const ROBOTS_AGENT: &str = "grok-search-mcp";
+
+/// The robots.txt location for the origin of `url`.
+pub fn robots_url(url: &Url) -> Option<Url> {
+    let mut robots = url.clone();
+    robots.set_path("/robots.txt");
+    robots.set_query(None);
+    robots.set_fragment(None);
+    robots.host_str()?;
+    Some(robots)
+}
+
+/// The path and query that rules are matched against, e.g. `/docs/page?id=1`.
+pub fn request_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// Whether `path` may be fetched under `robots_txt`. Rules from groups naming our agent apply,
+/// else those of the `*` groups; the longest matching rule wins and `Allow` wins ties.
+pub fn is_allowed(robots_txt: &str, path: &str) -> bool {
+    if path == "/robots.txt" {
+        return true;
+    }
+    let groups = parse_groups(robots_txt);
+    let ours: Vec<&Rule> = groups.iter()
+        .filter(|g| g.agents.iter().any(|a| a == ROBOTS_AGENT))
+        .flat_map(|g| &g.rules)
+        .collect();
+    let rules = if ours.is_empty() {
+        groups.iter().filter(|g| g.agents.iter().any(|a| a == "*")).flat_map(|g| &g.rules).collect()
+    } else {
+        ours
+    };
+
+    let mut best: Option<&Rule> = None;
+    for rule in rules.into_iter().filter(|r| pattern_matches(&r.pattern, path)) {
+        let better = match best {
+            None => true,
+            Some(b) => rule.pattern.len() > b.pattern.len() || (rule.pattern.len() == b.pattern.len() && rule.allow),
+        };
+        if better {
+            best = Some(rule);
+        }
+    }
+    best.is_none_or(|r| r.allow)
+}
+
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+/// Groups start at a run of `User-agent` lines and collect the rules that follow them.
+/// Agent names are reduced to their lowercased product token.
+fn parse_groups(robots_txt: &str) -> Vec<Group> {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut in_agents = false;
+    for line in robots_txt.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let value = value.trim();
+        match key.trim().to_lowercase().as_str() {
+            "user-agent" => {
+                let agent = value.split('/').next().unwrap_or_default().trim().to_lowercase();
+                match groups.last_mut() {
+                    Some(group) if in_agents => group.agents.push(agent),
+                    _ => groups.push(Group { agents: vec![agent], rules: Vec::new() }),
+                }
+                in_agents = true;
+            }
+            key @ ("allow" | "disallow") => {
+                in_agents = false;
+                // An empty Disallow allows everything, which is the same as no rule.
+                if let Some(group) = groups.last_mut()
+                    && !value.is_empty()
+                {
+                    group.rules.push(Rule { allow: key == "allow", pattern: value.to_string() });
+                }
+            }
+            _ => {}
+        }
+    }
+    groups
+}
+
+/// Prefix match where `*` matches any run of characters and a trailing `$` anchors the end.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = path.strip_prefix(first) else { return false };
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        let last = i + 1 == parts.len();
+        if last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcard_and_anchor_patterns() {
+        assert!(pattern_matches("/docs", "/docs/page"));
+        assert!(!pattern_matches("/docs", "/api/docs"));
+        assert!(pattern_matches("/*.php", "/a/b/index.php?x=1"));
+        assert!(pattern_matches("/*.php$", "/a/index.php"));
+        assert!(!pattern_matches("/*.php$", "/a/index.php?x=1"));
+        assert!(pattern_matches("/page$", "/page"));
+        assert!(!pattern_matches("/page$", "/pages"));
+        assert!(pattern_matches("/a*c*e", "/abcde"));
+        assert!(!pattern_matches("/a*c*e", "/abcd"));
+    }
+
+    #[test]
+    fn consecutive_user_agents_share_a_group() {
+        let robots = "User-agent: other\nUser-agent: grok-search-mcp/1.0\nDisallow: /private\n";
+        assert!(!is_allowed(robots, "/private/x"));
+        assert!(is_allowed(robots, "/public"));
+    }
+
+    #[test]
+    fn longest_match_wins_and_allow_wins_ties() {
+        let robots = "User-agent: *\nDisallow: /docs\nAllow: /docs/public\nAllow: /tie\nDisallow: /tie\n";
+        assert!(!is_allowed(robots, "/docs/private"));
+        assert!(is_allowed(robots, "/docs/public/page"));
+        assert!(is_allowed(robots, "/tie"));
+    }
+
+    #[test]
+    fn our_group_replaces_the_wildcard_group() {
+        let robots = "User-agent: *\nDisallow: /\n\nUser-agent: grok-search-mcp\nDisallow: /admin\n";
+        assert!(is_allowed(robots, "/page"));
+        assert!(!is_allowed(robots, "/admin"));
+        // Without a group of our own, the `*` rules apply.
+        let robots = "User-agent: otherbot\nDisallow: /\n\nUser-agent: *\nDisallow: /admin\n";
+        assert!(is_allowed(robots, "/page"));
+        assert!(!is_allowed(robots, "/admin"));
+        assert!(is_allowed(robots, "/robots.txt"));
+    }
+}
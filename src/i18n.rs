@@ -0,0 +1,102 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    Zh,
+}
+
+impl Lang {
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Zh => "zh",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_lowercase().as_str() {
+            "en" => Some(Self::En),
+            "zh" => Some(Self::Zh),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    StatusSuccess,
+    StatusFailed,
+    ConfigComplete,
+    ConfigUnverified,
+    ModelSwitched,
+    ModelSwitchFailed,
+    ModelNotFound,
+    ModelNotAllowed,
+    ModelListFailed,
+    ProfileSwitched,
+    ProfileSwitchFailed,
+    BuiltinDisabled,
+    BuiltinEnabled,
+    BuiltinCurrentlyDisabled,
+    BuiltinCurrentlyEnabled,
+    ConfigReset,
+    ConfigResetFailed,
+}
+
+/// Looks up a user-facing string. Templates use `{}` placeholders, filled by [`msg_fmt`].
+pub fn msg(key: Msg, lang: Lang) -> &'static str {
+    match (key, lang) {
+        (Msg::StatusSuccess, Lang::En) => "✅ Success",
+        (Msg::StatusSuccess, Lang::Zh) => "✅ 成功",
+        (Msg::StatusFailed, Lang::En) => "❌ Failed",
+        (Msg::StatusFailed, Lang::Zh) => "❌ 失败",
+        (Msg::ConfigComplete, Lang::En) => "✅ Configuration complete",
+        (Msg::ConfigComplete, Lang::Zh) => "✅ 配置完整",
+        (Msg::ConfigUnverified, Lang::En) => "❌ Configuration loaded, but the connection test failed",
+        (Msg::ConfigUnverified, Lang::Zh) => "❌ 配置已加载，但连接测试失败",
+        (Msg::ModelSwitched, Lang::En) => "Model switched from {} to {}",
+        (Msg::ModelSwitched, Lang::Zh) => "模型已从 {} 切换到 {}",
+        (Msg::ModelSwitchFailed, Lang::En) => "Failed to switch model: {}",
+        (Msg::ModelSwitchFailed, Lang::Zh) => "切换模型失败: {}",
+        (Msg::ModelNotFound, Lang::En) => "Model {} does not exist",
+        (Msg::ModelNotFound, Lang::Zh) => "模型 {} 不存在",
+        (Msg::ModelNotAllowed, Lang::En) => "Model {} is not allowed; allowed models: {}",
+        (Msg::ModelNotAllowed, Lang::Zh) => "模型 {} 不在允许列表中，允许的模型: {}",
+        (Msg::ModelListFailed, Lang::En) => "Could not fetch the model list for validation: {} (set validate=false to skip)",
+        (Msg::ModelListFailed, Lang::Zh) => "无法获取模型列表进行校验: {}（可设置 validate=false 跳过校验）",
+        (Msg::ProfileSwitched, Lang::En) => "Profile switched from {} to {}",
+        (Msg::ProfileSwitched, Lang::Zh) => "配置档案已从 {} 切换到 {}",
+        (Msg::ProfileSwitchFailed, Lang::En) => "Failed to switch profile: {}",
+        (Msg::ProfileSwitchFailed, Lang::Zh) => "切换配置档案失败: {}",
+        (Msg::BuiltinDisabled, Lang::En) => "Built-in tools disabled",
+        (Msg::BuiltinDisabled, Lang::Zh) => "官方工具已禁用",
+        (Msg::BuiltinEnabled, Lang::En) => "Built-in tools enabled",
+        (Msg::BuiltinEnabled, Lang::Zh) => "官方工具已启用",
+        (Msg::BuiltinCurrentlyDisabled, Lang::En) => "Built-in tools are currently disabled",
+        (Msg::BuiltinCurrentlyDisabled, Lang::Zh) => "官方工具当前已禁用",
+        (Msg::BuiltinCurrentlyEnabled, Lang::En) => "Built-in tools are currently enabled",
+        (Msg::BuiltinCurrentlyEnabled, Lang::Zh) => "官方工具当前已启用",
+        (Msg::ConfigReset, Lang::En) => "Configuration reset to defaults; restart the server to apply",
+        (Msg::ConfigReset, Lang::Zh) => "配置已恢复默认，重启服务后生效",
+        (Msg::ConfigResetFailed, Lang::En) => "Failed to reset configuration: {}",
+        (Msg::ConfigResetFailed, Lang::Zh) => "重置配置失败: {}",
+    }
+}
+
+/// Fills the `{}` placeholders of a message template in order.
+pub fn msg_fmt(key: Msg, lang: Lang, args: &[&dyn Display]) -> String {
+    let mut out = String::new();
+    let mut args = args.iter();
+    let mut parts = msg(key, lang).split("{}").peekable();
+    while let Some(part) = parts.next() {
+        out.push_str(part);
+        if parts.peek().is_some()
+            && let Some(arg) = args.next()
+        {
+            out.push_str(&arg.to_string());
+        }
+    }
+    out
+}
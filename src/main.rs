@@ -1,30 +1,126 @@
 mod config;
 mod error;
 mod grok;
+mod i18n;
 mod server;
 mod tools;
 
+use std::net::SocketAddr;
+use std::sync::Arc;
+
 use anyhow::Result;
-use rmcp::{transport::stdio, ServiceExt};
+use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
+use rmcp::transport::{stdio, StreamableHttpServerConfig, StreamableHttpService};
+use rmcp::ServiceExt;
 use tokio::signal;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing::Subscriber;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 
-use crate::config::Config;
+use crate::config::{Config, Transport};
+use crate::grok::GrokClient;
 use crate::server::GrokSearchServer;
 
+const USAGE: &str = "\
+Usage: grok-search-mcp [OPTIONS]
+
+Runs the Grok Search MCP server, configured through GROK_* environment variables.
+The transport is stdio unless GROK_TRANSPORT=sse.
+
+Options:
+  --check        Validate the configuration and test the API connection, then exit
+  -h, --help     Print this help and exit
+  -V, --version  Print the version and exit
+";
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::registry()
-        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
-        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
-        .init();
+    match std::env::args().nth(1).as_deref() {
+        None => {}
+        Some("-V" | "--version") => {
+            println!("grok-search-mcp {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Some("-h" | "--help") => {
+            print!("{USAGE}");
+            return Ok(());
+        }
+        // Undocumented: for generating client stubs without an MCP connection.
+        Some("--dump-schema") => {
+            println!("{}", serde_json::to_string_pretty(&GrokSearchServer::tool_schemas())?);
+            return Ok(());
+        }
+        Some("--check") => {
+            let ok = check_config().await;
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Some(arg) => {
+            eprint!("Unknown argument: {arg}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    }
 
-    tracing::info!("Starting Grok Search MCP Server v{}", env!("CARGO_PKG_VERSION"));
+    let config = load_config()?;
+    let _log_guard = init_tracing(&config);
 
-    let config = Config::load()?;
+    tracing::info!("Starting Grok Search MCP Server v{}", env!("CARGO_PKG_VERSION"));
     tracing::debug!("Configuration loaded: model={}", config.model);
 
+    let transport = config.transport;
+    let bind_addr = config.bind_addr;
     let server = GrokSearchServer::new(config);
+    #[cfg(unix)]
+    tokio::spawn(reload_on_hangup(server.clone()));
+
+    match transport {
+        Transport::Stdio => serve_stdio(server).await?,
+        Transport::Sse => serve_sse(server, bind_addr).await?,
+    }
+
+    tracing::info!("Grok Search MCP Server stopped");
+    Ok(())
+}
+
+/// `--check`: loads and validates the configuration, then tests the API connection, reporting to
+/// stderr without starting the MCP server. Returns whether every step passed.
+async fn check_config() -> bool {
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("✗ Configuration: [{}] {}", e.code(), e);
+            return false;
+        }
+    };
+    eprintln!("✓ Configuration loaded from environment and {}", Config::config_file_path().display());
+    eprintln!("  api_url: {} (requests go to {})", config.api_url, config.api_base());
+    eprintln!("  api_key: {}", config.mask_api_key());
+    eprintln!("  model:   {} (profile {})", config.model, config.profile);
+
+    let result = GrokClient::new(&config).test_connection().await;
+    if result.status == "success" {
+        eprintln!(
+            "✓ Connection: {} models listed in {} ms",
+            result.model_count.unwrap_or_default(),
+            result.response_time_ms.unwrap_or_default()
+        );
+        true
+    } else {
+        eprintln!(
+            "✗ Connection: [{}] {}",
+            result.error_code.as_deref().unwrap_or("UNKNOWN"),
+            result.message.as_deref().unwrap_or_default()
+        );
+        if let Some(hint) = result.hint {
+            eprintln!("  hint: {hint}");
+        }
+        false
+    }
+}
+
+/// Serves the single client that spawned this process, until it disconnects or exits.
+async fn serve_stdio(server: GrokSearchServer) -> Result<()> {
     let service = server.serve(stdio()).await?;
 
     tokio::select! {
@@ -40,11 +136,84 @@ async fn main() -> Result<()> {
             tracing::info!("Parent process exited");
         }
     }
+    Ok(())
+}
 
-    tracing::info!("Grok Search MCP Server stopped");
+/// Serves any number of remote clients over HTTP at `/mcp`, streaming responses as SSE.
+/// Every session shares the same state, so config reloads, the cache and metrics apply to all of them.
+async fn serve_sse(server: GrokSearchServer, addr: SocketAddr) -> Result<()> {
+    let config = StreamableHttpServerConfig::default();
+    let sessions_done = config.cancellation_token.clone();
+    let service = StreamableHttpService::new(
+        move || Ok(server.clone()),
+        Arc::new(LocalSessionManager::default()),
+        config,
+    );
+    let router = axum::Router::new().nest_service("/mcp", service);
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("Listening for MCP clients on http://{}/mcp", listener.local_addr()?);
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            tracing::info!("Shutdown signal received");
+            sessions_done.cancel();
+        })
+        .await?;
     Ok(())
 }
 
+/// Logs to stderr, plus a daily-rotated file in `GROK_LOG_DIR` at `GROK_LOG_LEVEL` when set.
+/// The returned guard flushes the file writer on drop and must live until shutdown.
+/// Loads the configuration with a stderr-only subscriber in place, so warnings raised while
+/// loading are not lost before `init_tracing` knows where the log file goes.
+fn load_config() -> crate::error::Result<Config> {
+    // Not `SubscriberInitExt::set_default`, which would also claim the `log` bridge that `init` sets up.
+    let _guard = tracing::subscriber::set_default(tracing_subscriber::registry().with(stderr_layer()));
+    Config::load()
+}
+
+fn stderr_layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+}
+
+fn init_tracing(config: &Config) -> Option<WorkerGuard> {
+    let (file_layer, guard) = match &config.log_dir {
+        Some(dir) => {
+            let appender = tracing_appender::rolling::daily(dir, "grok-search.log");
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = tracing_subscriber::fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_filter(parse_level(&config.log_level));
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    tracing_subscriber::registry()
+        .with(stderr_layer())
+        .with(file_layer)
+        .init();
+    guard
+}
+
+fn parse_level(level: &str) -> LevelFilter {
+    match level {
+        "TRACE" => LevelFilter::TRACE,
+        "DEBUG" => LevelFilter::DEBUG,
+        "WARN" | "WARNING" => LevelFilter::WARN,
+        "ERROR" | "CRITICAL" => LevelFilter::ERROR,
+        "OFF" => LevelFilter::OFF,
+        _ => LevelFilter::INFO,
+    }
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         signal::ctrl_c().await.expect("failed to install Ctrl+C handler");
@@ -67,6 +236,25 @@ async fn shutdown_signal() {
     }
 }
 
+/// Reloads the configuration on each SIGHUP without dropping the MCP connection.
+/// A failed reload keeps the current configuration.
+#[cfg(unix)]
+async fn reload_on_hangup(server: GrokSearchServer) {
+    let mut hangup = match signal::unix::signal(signal::unix::SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Failed to install SIGHUP handler: {}", e);
+            return;
+        }
+    };
+    while hangup.recv().await.is_some() {
+        tracing::info!("SIGHUP received, reloading configuration");
+        if let Err(e) = server.reload() {
+            tracing::error!("Configuration reload failed, keeping the current settings: {}", e);
+        }
+    }
+}
+
 async fn parent_process_exited() {
     #[cfg(windows)]
     {
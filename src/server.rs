@@ -1,21 +1,156 @@
 use crate::config::Config;
-use crate::grok::GrokClient;
-use crate::tools::{GetConfigInfoParams, SwitchModelParams, ToggleBuiltinToolsParams, WebFetchParams, WebSearchParams};
+use crate::error::GrokError;
+use crate::grok::{
+    CallOptions, FetchFormat, FetchMode, FetchOptions, GrokClient, GrokResponse, Progress, ProgressSender, SafeSearch, SearchOptions, SortOrder, REQUEST_TIMEOUT,
+};
+use crate::i18n::{msg, msg_fmt, Msg};
+use crate::tools::{
+    ClearCacheParams, DebugStreamParams, GetConfigInfoParams, HealthCheckParams, ListModelsParams, MetricsParams, ResetConfigParams, SwitchModelParams, SwitchProfileParams, TestModelParams, ToggleBuiltinToolsParams,
+    WebFetchParams, WebSearchParams,
+};
 
+use rmcp::handler::server::router::tool::ToolRouter;
+use rmcp::handler::server::tool::ToolCallContext;
 use rmcp::handler::server::wrapper::Parameters;
-use rmcp::model::{Implementation, ServerCapabilities, ServerInfo};
-use rmcp::{tool, tool_handler, tool_router, ErrorData as McpError, ServerHandler};
+use rmcp::model::{
+    AnnotateAble, CallToolRequestParam, CallToolResult, Content, Implementation, ListResourcesResult, ListToolsResult, Meta,
+    PaginatedRequestParam, ProgressNotificationParam, RawContent, RawResource, ReadResourceRequestParam, ReadResourceResult,
+    ResourceContents, ServerCapabilities, ServerInfo,
+};
+use rmcp::service::RequestContext;
+use rmcp::{tool, tool_router, ErrorData as McpError, RoleServer, ServerHandler};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::Instrument;
 
-#[derive(Clone)]
-pub struct GrokSearchServer {
+/// A loaded configuration and the client built from it, swapped as a unit on reload.
+pub struct ServerState {
     pub config: Config,
     pub client: GrokClient,
 }
 
+/// How much of a `test_model` result is returned.
+const TEST_MODEL_PREVIEW_CHARS: usize = 1000;
+
+#[derive(Clone)]
+pub struct GrokSearchServer {
+    state: Arc<RwLock<Arc<ServerState>>>,
+}
+
 impl GrokSearchServer {
     pub fn new(config: Config) -> Self {
         let client = GrokClient::new(&config);
-        Self { config, client }
+        if config.warmup {
+            warm_up(client.clone());
+        }
+        Self { state: Arc::new(RwLock::new(Arc::new(ServerState { config, client }))) }
+    }
+
+    /// The state in effect now; a call keeps its snapshot even if a reload happens meanwhile.
+    fn state(&self) -> Arc<ServerState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Re-reads the environment and config file and rebuilds the API client. In-flight calls
+    /// finish on the old client; the search cache and circuit breaker start fresh.
+    pub fn reload(&self) -> crate::error::Result<()> {
+        let config = Config::load()?;
+        let previous = self.state();
+        tracing::info!("Reloading configuration: {} -> {}", describe(&previous.config), describe(&config));
+        let client = GrokClient::new(&config).with_metrics_from(&previous.client);
+        if config.warmup {
+            warm_up(client.clone());
+        }
+        *self.state.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(ServerState { config, client });
+        Ok(())
+    }
+
+    /// The tools on offer; debugging tools are hidden unless `GROK_DEBUG` is enabled.
+    fn tool_routes(&self) -> ToolRouter<Self> {
+        let mut router = Self::tool_router();
+        if !self.state().config.debug_enabled {
+            router.remove_route("debug_stream");
+        }
+        router
+    }
+
+    /// The JSON Schema of every tool's parameters, keyed by tool name, without needing a config.
+    pub fn tool_schemas() -> serde_json::Value {
+        let schemas: serde_json::Map<String, serde_json::Value> = Self::tool_router().list_all().into_iter()
+            .map(|tool| (tool.name.to_string(), serde_json::Value::Object((*tool.input_schema).clone())))
+            .collect();
+        serde_json::Value::Object(schemas)
+    }
+
+    /// The loaded settings that shape request behavior, as they are actually in effect.
+    /// Grouped into sub-objects, each its own `json!`, to stay within the default macro recursion limit.
+    fn effective_config(c: &Config) -> serde_json::Value {
+        let retry = serde_json::json!({
+            "retry_max_attempts": c.retry_max_attempts,
+            "retry_multiplier": c.retry_multiplier,
+            "retry_max_wait": c.retry_max_wait,
+            "retry_jitter": c.retry_jitter.name(),
+            "retry_on": c.retry_on.iter().map(|c| c.name()).collect::<Vec<_>>(),
+            "hedge_delay_ms": c.hedge_delay_ms,
+            "circuit_threshold": c.circuit_threshold,
+            "circuit_cooldown_secs": c.circuit_cooldown_secs,
+        });
+        let timeouts = serde_json::json!({
+            "connect_timeout_secs": c.connect_timeout_secs,
+            "read_timeout_secs": c.read_timeout_secs,
+            "stream_stall_secs": c.stream_stall_secs,
+            "stream_max_secs": c.stream_max_secs,
+            "partial_on_timeout": c.partial_on_timeout,
+            "request_timeout_secs": REQUEST_TIMEOUT,
+        });
+        let limits = serde_json::json!({
+            "max_concurrency": c.max_concurrency,
+            "max_qps": c.max_qps,
+            "fetch_concurrency": c.fetch_concurrency,
+            "max_content_mb": c.max_content_mb,
+        });
+        let cache = serde_json::json!({
+            "cache_ttl_secs": c.cache_ttl_secs,
+            "fetch_cache_ttl_secs": c.fetch_cache_ttl_secs,
+        });
+        let results = serde_json::json!({
+            "fetch_min_chars": c.fetch_min_chars,
+            "retry_empty_results": c.retry_empty_results,
+            "default_min_results": c.default_min_results,
+            "default_max_results": c.default_max_results,
+        });
+        let prompt = serde_json::json!({
+            "custom_search_prompt": c.search_prompt.is_some(),
+            "custom_fetch_prompt": c.fetch_prompt.is_some(),
+            "search_suffix": &c.search_suffix,
+            "fetch_suffix": &c.fetch_suffix,
+            "time_context": c.time_context.name(),
+            "time_keywords": &c.time_keywords,
+            "timezone": &c.timezone,
+            "time_format": &c.time_format,
+        });
+        serde_json::json!({
+            "transport": c.transport.name(),
+            "allowed_models": &c.allowed_models,
+            "api_style": c.api_style.name(),
+            "api_path_style": c.api_path_style.name(),
+            "api_base": c.api_base(),
+            "user_agent": &c.user_agent,
+            "org_id": &c.org_id,
+            "project_id": &c.project_id,
+            "streaming": c.streaming,
+            "dry_run": c.dry_run,
+            "warmup": c.warmup,
+            "redact_queries": c.redact_queries,
+            "lang": c.lang.code(),
+            "retry": retry,
+            "timeouts": timeouts,
+            "limits": limits,
+            "cache": cache,
+            "results": results,
+            "prompt": prompt,
+        })
     }
 }
 
@@ -27,16 +162,95 @@ impl GrokSearchServer {
 
     The `query` should be a clear, self-contained natural-language search query.
     When helpful, include constraints such as topic, time range, language, or domain.
+    Queries over 2000 characters are rejected unless `truncate_query` is set, which cuts them at a word boundary.
+
+    The `platform` should be the platforms to focus the search on, comma-separated (up to 5), such as
+    "Twitter", "GitHub,Reddit", "Hacker News" or "Stack Overflow". Unknown names are rejected with the list
+    of known platforms; prefix a name with "raw:" (e.g. "raw:Mastodon") to use any other platform verbatim.
+
+    The `min_results` and `max_results` should be the minimum and maximum number of results to return
+    (defaults 3 and 10 unless the server sets `GROK_DEFAULT_MIN_RESULTS` / `GROK_DEFAULT_MAX_RESULTS`).
+
+    The `start_date` and `end_date` optionally restrict results to a publication window (ISO 8601 dates,
+    e.g. "2024-01-31"). Either bound may be omitted to leave that side of the range open.
+
+    The `exclude_domains` optionally lists bare hostnames (e.g. "pinterest.com") whose results should be skipped.
+
+    The `include_domains` optionally restricts results to an allowlist of bare hostnames. It takes
+    precedence over `exclude_domains`, and a domain may not appear in both.
+
+    The `language` optionally restricts results to one language, given as an ISO 639-1 code such as "en", "zh" or "ja".
 
-    The `platform` should be the platforms which you should focus on searching, such as "Twitter", "GitHub", "Reddit", etc.
+    The `sort` orders results by "relevance" (default) or "recency"; with "recency" each result
+    also carries a `published_at` date.
 
-    The `min_results` and `max_results` should be the minimum and maximum number of results to return.
-    "#)]
-    pub async fn web_search(&self, Parameters(params): Parameters<WebSearchParams>) -> Result<String, McpError> {
-        params.validate().map_err(|msg| McpError::invalid_params(msg, None))?;
-        self.client.search(params.query.trim(), params.platform.trim(), params.min_results, params.max_results)
+    The `safe_search` level filters adult content: "off", "moderate" (default) or "strict"
+    (suitable for a school audience). Filtering is best effort, applied through the prompt.
+
+    Set `strict_json` to guarantee parseable JSON output: invalid output is sent back to the model
+    once for correction, and the call fails if it is still invalid. The result is then a JSON array
+    of objects with `title`, `url`, `snippet`, `published_date` and `source`, each null when unknown.
+
+    Set `include_sources` to also get a bibliography: the output becomes an object with a `results`
+    array and a `sources` array of `{title, url}` covering every source consulted, including those
+    only mentioned inline. With `strict_json`, output missing either array counts as invalid.
+
+    With `dedupe` (default true), results in a JSON array that repeat an earlier `url`/`link` are removed;
+    with `include_sources`, both the `results` and the `sources` arrays are deduplicated.
+
+    The `model` optionally selects the model for this call, overriding `GROK_SEARCH_MODEL` and the configured model.
+
+    The `context` optionally carries earlier turns of the conversation, oldest first, as `{role, content}`
+    objects with role "user" or "assistant" (up to 20 messages, 20000 characters in total). They are sent
+    ahead of the query so follow-ups like "what about the second one?" are understood.
+
+    The `timeout_secs` optionally overrides the request timeout for this call (5-300 seconds, default 120).
+
+    Set `stream` to receive partial output early: when the request carries a progress token, each
+    text fragment is sent as a progress notification message as it arrives. The full result is still returned.
+    Once a fragment has been sent, a failed stream is not retried, so fragments never repeat.
+
+    A second content block carries JSON metadata: the responding `model`, token `usage` and,
+    when pricing is configured for the model, `estimated_cost_usd`.
+
+    Errors are prefixed with their class, e.g. "[TIMEOUT]", "[RATE_LIMIT]", "[AUTH_ERROR]" or "[SERVER_ERROR]".
+    "#, annotations(read_only_hint = true, open_world_hint = true))]
+    pub async fn web_search(&self, Parameters(params): Parameters<WebSearchParams>, ctx: RequestContext<RoleServer>) -> Result<CallToolResult, McpError> {
+        let state = self.state();
+        let params = params.with_result_defaults(state.config.default_min_results, state.config.default_max_results);
+        params.validate().map_err(invalid_params)?;
+        check_model_allowed(&state.config, params.model().as_deref())?;
+        let opts = SearchOptions {
+            platforms: params.platforms(),
+            min_results: params.min_results(),
+            max_results: params.max_results(),
+            start_date: params.start_date(),
+            end_date: params.end_date(),
+            exclude_domains: params.exclude_domains(),
+            include_domains: params.include_domains(),
+            language: params.language(),
+            sort: SortOrder::from_name(&params.sort).unwrap_or_default(),
+            safe_search: SafeSearch::from_name(&params.safe_search).unwrap_or_default(),
+            strict_json: params.strict_json,
+            include_sources: params.include_sources,
+            dedupe: params.dedupe,
+            model: params.model(),
+            context: params.context(),
+        };
+        let call = CallOptions {
+            progress: progress_sender(&ctx),
+            timeout: params.timeout_secs.map(Duration::from_secs),
+            stream_deltas: params.stream,
+            cancel: Some(ctx.ct.clone()),
+        };
+        let query = params.query();
+        if query.len() < params.query.trim().len() {
+            tracing::info!("Query truncated from {} to {} characters", params.query.trim().chars().count(), query.chars().count());
+        }
+        state.client.search(&query, &opts, &call)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))
+            .map_err(tool_error)
+            .and_then(|resp| response_result(resp, &state.config))
     }
 
     #[tool(description = r#"
@@ -44,6 +258,31 @@ impl GrokSearchServer {
     as a structured Markdown document.
     The `url` should be a valid HTTP/HTTPS web address pointing to the target page.
     Ensure the URL is complete and accessible (not behind authentication or paywalls).
+    To fetch several pages at once, pass them in `urls` (max 20 in total); the result is then a
+    JSON array of `{url, status, content}` objects, and a failed page does not abort the batch
+    (it also carries an `error_code` such as "TIMEOUT", "RATE_LIMIT" or "AUTH_ERROR").
+    Errors are prefixed with the same class, e.g. "[RATE_LIMIT] Rate limited by API".
+    The `format` selects the output: "markdown" (default, described below), "text" (plain text
+    without markup, suited for embeddings), or "html" (the raw page source).
+    Set `mode` to "summary" for a TL;DR instead of the full page: a short bulleted summary
+    followed by the key links, always in Markdown.
+    The `max_chars` optionally sets a character budget (at least 200): the page is condensed to
+    roughly that length and the result is cut off beyond it.
+    The `model` optionally selects the model for this call, overriding `GROK_FETCH_MODEL` and the configured model.
+    The `timeout_secs` optionally overrides the request timeout (5-300 seconds, default 120); in a
+    batch it applies to each page separately.
+    The `css_selector` optionally narrows the result to one region of the page, such as "article",
+    "main" or "table.pricing": only the matching fragment is returned, in the selected format.
+    Set `clean_url` to strip tracking query parameters (`utm_*`, `fbclid`, `gclid` and similar) before
+    fetching, which also keeps them out of logs.
+    Set `respect_robots` to check the site's robots.txt first; disallowed pages fail with
+    "[ROBOTS_DISALLOWED]" without calling the model.
+    With `GROK_FETCH_MIN_CHARS` set, a near-empty result is retried once and then fails with
+    "[CONTENT_TOO_SHORT]", which usually means the page needs JavaScript rendering.
+    When a result is cut off (by `max_chars` or the size cap), it ends with a "[truncated: ...]" note
+    and its metadata (or batch entry) carries a `continue_token`. Call again with just that
+    `continue_token` to get the next chunk, which reuses the original URL and options and may carry
+    its own token. Tokens expire after an hour; an unknown one fails with "[INVALID_CONTINUE_TOKEN]".
     The function will:
     - Retrieve the full HTML content from the URL
     - Parse and extract all meaningful content (text, images, links, tables, code blocks)
@@ -57,12 +296,46 @@ impl GrokSearchServer {
         - Metadata header (source URL, title, fetch timestamp)
         - Table of Contents (if applicable)
         - Complete page content with preserved structure
-    "#)]
-    pub async fn web_fetch(&self, Parameters(params): Parameters<WebFetchParams>) -> Result<String, McpError> {
-        params.validate().map_err(|msg| McpError::invalid_params(msg, None))?;
-        self.client.fetch(params.url.trim())
+        A second content block carries JSON metadata: the responding `model`, token `usage` and,
+        when pricing is configured for the model, `estimated_cost_usd`.
+    "#, annotations(read_only_hint = true, open_world_hint = true))]
+    pub async fn web_fetch(&self, Parameters(params): Parameters<WebFetchParams>, ctx: RequestContext<RoleServer>) -> Result<CallToolResult, McpError> {
+        let state = self.state();
+        params.validate().map_err(invalid_params)?;
+        check_model_allowed(&state.config, params.model().as_deref())?;
+        let urls = params.all_urls();
+        let opts = FetchOptions {
+            format: FetchFormat::from_name(&params.format).unwrap_or_default(),
+            mode: FetchMode::from_name(&params.mode).unwrap_or_default(),
+            model: params.model(),
+            max_chars: params.max_chars,
+            respect_robots: params.respect_robots,
+            css_selector: params.css_selector(),
+        };
+        let call = CallOptions {
+            progress: progress_sender(&ctx),
+            timeout: params.timeout_secs.map(Duration::from_secs),
+            stream_deltas: false,
+            cancel: Some(ctx.ct.clone()),
+        };
+
+        if let Some(token) = params.continue_token() {
+            return state.client.fetch_continue(&token, &call)
+                .await
+                .map_err(tool_error)
+                .and_then(|resp| response_result(resp, &state.config));
+        }
+
+        if params.is_batch() {
+            let outcomes = state.client.fetch_many(&urls, &opts, &call).await;
+            let json = serde_json::to_string_pretty(&outcomes).map_err(internal_error)?;
+            return Ok(CallToolResult::success(vec![Content::text(json)]));
+        }
+
+        state.client.fetch(&urls[0], &opts, &call)
             .await
-            .map_err(|e| McpError::internal_error(e.to_string(), None))
+            .map_err(tool_error)
+            .and_then(|resp| response_result(resp, &state.config))
     }
 
     #[tool(description = r#"
@@ -74,39 +347,157 @@ impl GrokSearchServer {
     - Debugging configuration issues
     - Checking the current API endpoint and settings
 
+    Parameters
+    ----------
+    format : str, optional
+        "pretty" (default) for indented JSON or "compact" for a single line
+
     Returns
     -------
     str
         A JSON-encoded string containing configuration details:
         - `api_url`: The configured Grok API endpoint
-        - `api_key`: The API key (masked for security, showing only first and last 4 characters)
+        - `api_key`: The API key (masked with its true length and at most the first and last 4 characters, e.g. `xai-…(84)…9f2c`)
         - `model`: The currently selected model for search and fetch operations
+        - `search_model` / `fetch_model`: The models actually used per tool (`GROK_SEARCH_MODEL` / `GROK_FETCH_MODEL`)
+        - `profile`: The active named profile ("default" when none is selected)
         - `debug_enabled`: Whether debug mode is enabled
         - `log_level`: Current logging level
         - `log_dir`: Directory where logs are stored
-        - `config_status`: Overall configuration status (✅ complete or ❌ error)
+        - `config_status`: Overall configuration status (✅ complete or ❌ error), for display
+        - `config_complete`: The same status as a boolean, for scripts: true when the loaded
+          configuration also passed the connection test
         - `connection_test`: Result of testing API connectivity to /models endpoint
           - `status`: Connection status
           - `message`: Status message with model count
           - `response_time_ms`: API response time in milliseconds
-    "#)]
-    pub async fn get_config_info(&self, _params: Parameters<GetConfigInfoParams>) -> Result<String, McpError> {
-        let config_status = "✅ 配置完整".to_string();
-        let connection_test = self.client.test_connection().await;
+          - `error_code` / `hint`: On failure, the error class (e.g. "AUTH_ERROR", "TLS_ERROR") and what to check next
+        - `circuit_breaker`: Breaker `state` ("closed", "open" or "half_open"), `consecutive_failures`
+          and, while open, `retry_in_secs`
+        - `effective_config`: Resolved settings; retry, timeout, concurrency, caching, result and prompt
+          settings are grouped under `retry`, `timeouts`, `limits`, `cache`, `results` and `prompt`
+          after environment and config file precedence
+    "#, annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false))]
+    pub async fn get_config_info(&self, Parameters(params): Parameters<GetConfigInfoParams>) -> Result<String, McpError> {
+        params.validate().map_err(invalid_params)?;
+        let state = self.state();
+        // Config::load already refuses a missing URL or key, so only the connection test can still fail.
+        let connection_test = state.client.test_connection().await;
+        let config_complete = connection_test.status == "success";
+        let config_status = msg(if config_complete { Msg::ConfigComplete } else { Msg::ConfigUnverified }, state.config.lang);
 
         let payload = serde_json::json!({
-            "api_url": &self.config.api_url,
-            "api_key": self.config.mask_api_key(),
-            "model": &self.config.model,
-            "debug_enabled": self.config.debug_enabled,
-            "log_level": &self.config.log_level,
-            "log_dir": self.config.log_dir.clone().unwrap_or_default(),
+            "api_url": &state.config.api_url,
+            "api_key": state.config.mask_api_key(),
+            "model": &state.config.model,
+            "search_model": state.config.search_model.as_ref().unwrap_or(&state.config.model),
+            "fetch_model": state.config.fetch_model.as_ref().unwrap_or(&state.config.model),
+            "profile": &state.config.profile,
+            "debug_enabled": state.config.debug_enabled,
+            "log_level": &state.config.log_level,
+            "log_dir": state.config.log_dir.clone().unwrap_or_default(),
             "config_file": Config::config_file_path().to_string_lossy(),
             "config_status": config_status,
+            "config_complete": config_complete,
             "connection_test": connection_test,
+            "circuit_breaker": state.client.circuit_status(),
+            "effective_config": Self::effective_config(&state.config),
+        });
+
+        if params.is_compact() {
+            serde_json::to_string(&payload).map_err(internal_error)
+        } else {
+            serde_json::to_string_pretty(&payload).map_err(internal_error)
+        }
+    }
+
+    #[tool(description = r#"
+    Checks API health and returns machine-readable status for automated monitoring.
+
+    Returns
+    -------
+    str
+        A JSON-encoded string containing:
+        - `healthy`: True when the API is reachable, the configured model is available and the circuit breaker is not open
+        - `latency_ms`: Response time of the /models request in milliseconds
+        - `model`: The configured model
+        - `model_reachable`: Whether the configured model is listed by /models
+        - `circuit_breaker`: Breaker `state`, `consecutive_failures` and, while open, `retry_in_secs`
+        - `rate_limit`: With `GROK_MAX_QPS`, the `max_qps` and currently `available_tokens`; otherwise null
+        - `checks`: Individual checks, each with `name`, `passed` and an optional `error_code`
+    "#, annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false))]
+    pub async fn health_check(&self, _params: Parameters<HealthCheckParams>) -> Result<String, McpError> {
+        let state = self.state();
+        let connection = state.client.test_connection().await;
+        let connected = connection.status == "success";
+        let circuit = state.client.circuit_status();
+        let circuit_closed = circuit.state != "open";
+
+        let (model_reachable, model_error) = if connected {
+            match state.client.list_models().await {
+                Ok(models) if models.contains(&state.config.model) => (true, None),
+                Ok(_) => (false, Some("MODEL_NOT_FOUND".to_string())),
+                Err(_) => (false, Some("MODEL_LIST_FAILED".to_string())),
+            }
+        } else {
+            (false, Some("SKIPPED".to_string()))
+        };
+
+        let payload = serde_json::json!({
+            "healthy": connected && model_reachable && circuit_closed,
+            "latency_ms": connection.response_time_ms,
+            "model": &state.config.model,
+            "model_reachable": model_reachable,
+            "circuit_breaker": circuit,
+            "rate_limit": state.client.rate_limit_status(),
+            "checks": [
+                {
+                    "name": "api_connection",
+                    "passed": connected,
+                    "error_code": connection.error_code,
+                },
+                {
+                    "name": "model_available",
+                    "passed": model_reachable,
+                    "error_code": model_error,
+                },
+                {
+                    "name": "circuit_breaker",
+                    "passed": circuit_closed,
+                    "error_code": if circuit_closed { None } else { Some("CIRCUIT_OPEN") },
+                },
+            ],
+        });
+
+        serde_json::to_string_pretty(&payload).map_err(internal_error)
+    }
+
+    #[tool(description = r#"
+    Lists the models available from the configured Grok API endpoint.
+
+    Use this to discover valid model IDs before calling `switch_model`.
+
+    Returns
+    -------
+    str
+        A JSON-encoded string containing:
+        - `models`: Model IDs reported by the /models endpoint, sorted alphabetically
+        - `count`: Number of models
+        - `current_model`: The currently selected model
+    "#, annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false))]
+    pub async fn list_models(&self, _params: Parameters<ListModelsParams>) -> Result<String, McpError> {
+        let state = self.state();
+        let models = state.client.list_models()
+            .await
+            .map_err(tool_error)?;
+
+        let payload = serde_json::json!({
+            "models": models,
+            "count": models.len(),
+            "current_model": &state.config.model,
         });
 
-        serde_json::to_string_pretty(&payload).map_err(|e| McpError::internal_error(e.to_string(), None))
+        serde_json::to_string_pretty(&payload).map_err(internal_error)
     }
 
     #[tool(description = r#"
@@ -121,6 +512,8 @@ impl GrokSearchServer {
     ----------
     model : str
         The model ID to switch to (e.g., "grok-4-fast", "grok-2-latest", "grok-vision-beta")
+    validate : bool, optional
+        Check the model against the /models endpoint before saving (default true)
 
     Returns
     -------
@@ -129,30 +522,300 @@ impl GrokSearchServer {
         - `status`: Success or error status
         - `previous_model`: The model that was being used before
         - `current_model`: The newly selected model
+        - `validated`: Whether the model was checked against the /models endpoint
+        - `available_models`: Valid model IDs (only when validation rejects the model)
         - `message`: Status message
         - `config_file`: Path where the model preference is saved
-    "#)]
+    "#, annotations(read_only_hint = false, destructive_hint = false, idempotent_hint = true, open_world_hint = false))]
     pub async fn switch_model(&self, Parameters(params): Parameters<SwitchModelParams>) -> Result<String, McpError> {
-        params.validate().map_err(|msg| McpError::invalid_params(msg, None))?;
+        let state = self.state();
+        params.validate().map_err(invalid_params)?;
 
-        let previous = self.config.model.clone();
+        let lang = state.config.lang;
+        let previous = state.config.model.clone();
         let next = params.model.trim().to_string();
+        let validate = params.validate.unwrap_or(true);
+
+        if !state.config.is_model_allowed(&next) {
+            let payload = serde_json::json!({
+                "status": msg(Msg::StatusFailed, lang),
+                "validated": false,
+                "message": msg_fmt(Msg::ModelNotAllowed, lang, &[&next, &state.config.allowed_models.join(", ")]),
+                "allowed_models": state.config.allowed_models,
+            });
+            return serde_json::to_string_pretty(&payload).map_err(internal_error);
+        }
+
+        if validate {
+            let rejection = match state.client.list_models().await {
+                Ok(models) if models.contains(&next) => None,
+                Ok(models) => Some(serde_json::json!({
+                    "status": msg(Msg::StatusFailed, lang),
+                    "validated": true,
+                    "message": msg_fmt(Msg::ModelNotFound, lang, &[&next]),
+                    "available_models": models,
+                })),
+                Err(e) => Some(serde_json::json!({
+                    "status": msg(Msg::StatusFailed, lang),
+                    "validated": false,
+                    "message": msg_fmt(Msg::ModelListFailed, lang, &[&e]),
+                })),
+            };
+            if let Some(payload) = rejection {
+                return serde_json::to_string_pretty(&payload).map_err(internal_error);
+            }
+        }
 
-        let payload = match Config::save_model(&next) {
+        // Applied at once, so the next call already uses the new model.
+        let payload = match Config::save_model(&next).and_then(|()| self.reload()) {
             Ok(()) => serde_json::json!({
-                "status": "✅ 成功",
+                "status": msg(Msg::StatusSuccess, lang),
                 "previous_model": previous,
                 "current_model": next,
-                "message": format!("模型已从 {} 切换到 {}", previous, next),
+                "validated": validate,
+                "message": msg_fmt(Msg::ModelSwitched, lang, &[&previous, &next]),
                 "config_file": Config::config_file_path().to_string_lossy(),
             }),
             Err(e) => serde_json::json!({
-                "status": "❌ 失败",
-                "message": format!("切换模型失败: {}", e),
+                "status": msg(Msg::StatusFailed, lang),
+                "validated": validate,
+                "message": msg_fmt(Msg::ModelSwitchFailed, lang, &[&e]),
             }),
         };
 
-        serde_json::to_string_pretty(&payload).map_err(|e| McpError::internal_error(e.to_string(), None))
+        serde_json::to_string_pretty(&payload).map_err(internal_error)
+    }
+
+    #[tool(description = r#"
+    Debugging aid, only listed when GROK_DEBUG is enabled: runs one streaming search or fetch
+    and returns the raw SSE body instead of the parsed content.
+
+    Parameters
+    ----------
+    kind : str
+        "search" (default) or "fetch"
+    input : str
+        The search query, or the URL to fetch
+    max_lines : int
+        Stop after this many lines (1-5000, default 500)
+
+    Returns
+    -------
+    str
+        A JSON-encoded array of the body's lines exactly as received, including `data:` payloads,
+        `:` keepalive comments, `event:` fields and the empty lines that end events.
+    "#, annotations(read_only_hint = true, open_world_hint = true))]
+    pub async fn debug_stream(&self, Parameters(params): Parameters<DebugStreamParams>) -> Result<String, McpError> {
+        let state = self.state();
+        if !state.config.debug_enabled {
+            return Err(invalid_params("debug_stream requires GROK_DEBUG=true".into()));
+        }
+        params.validate().map_err(invalid_params)?;
+        let lines = state.client.raw_stream(params.is_fetch(), params.input.trim(), params.max_lines)
+            .await
+            .map_err(tool_error)?;
+        serde_json::to_string_pretty(&lines).map_err(internal_error)
+    }
+
+    #[tool(description = r#"
+    Runs one probe search against a model without switching to it or persisting anything.
+
+    Use this to compare a candidate model before calling `switch_model`. The probe bypasses
+    the search cache so the latency is real.
+
+    Parameters
+    ----------
+    model : str
+        The model ID to probe
+    query : str
+        The search query to run
+
+    Returns
+    -------
+    str
+        A JSON-encoded string containing:
+        - `status`: Success or error status
+        - `model`: The model that responded
+        - `latency_ms`: Time for the whole request in milliseconds
+        - `usage`: Token usage, when reported
+        - `estimated_cost_usd`: Cost from `GROK_PRICING`, omitted when the model is not priced
+        - `result`: The output, cut to 1000 characters
+        - `truncated`: Whether `result` was cut
+        - `error_code` / `message`: Error class and details when the probe failed
+    "#, annotations(read_only_hint = true, open_world_hint = true))]
+    pub async fn test_model(&self, Parameters(params): Parameters<TestModelParams>) -> Result<String, McpError> {
+        let state = self.state();
+        params.validate().map_err(invalid_params)?;
+
+        let lang = state.config.lang;
+        let model = params.model.trim();
+        check_model_allowed(&state.config, Some(model))?;
+        let start = std::time::Instant::now();
+        let result = state.client.probe(model, params.query.trim()).await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        let payload = match result {
+            Ok(resp) => {
+                let cost = estimated_cost(&resp, &state.config);
+                let truncated = resp.content.chars().count() > TEST_MODEL_PREVIEW_CHARS;
+                let preview: String = resp.content.chars().take(TEST_MODEL_PREVIEW_CHARS).collect();
+                let mut payload = serde_json::json!({
+                    "status": msg(Msg::StatusSuccess, lang),
+                    "model": resp.model,
+                    "latency_ms": latency_ms,
+                    "usage": resp.usage,
+                    "result": preview,
+                    "truncated": truncated,
+                });
+                if let Some(cost) = cost {
+                    payload["estimated_cost_usd"] = serde_json::json!(cost);
+                }
+                payload
+            }
+            Err(e) => serde_json::json!({
+                "status": msg(Msg::StatusFailed, lang),
+                "model": model,
+                "latency_ms": latency_ms,
+                "error_code": e.code(),
+                "message": e.to_string(),
+            }),
+        };
+
+        serde_json::to_string_pretty(&payload).map_err(internal_error)
+    }
+
+    #[tool(description = r#"
+    Switches the active named profile (API endpoint, key and model) and persists the selection.
+
+    Profiles are defined under `profiles` in the config file; "default" restores the
+    top-level settings and environment variables. The new profile takes effect immediately.
+
+    Parameters
+    ----------
+    name : str
+        The profile name to activate
+
+    Returns
+    -------
+    str
+        A JSON-encoded string containing:
+        - `status`: Success or error status
+        - `previous_profile`: The profile that was active before
+        - `current_profile`: The newly selected profile
+        - `available_profiles`: All profiles defined in the config file
+        - `message`: Status message
+        - `config_file`: Path where the selection is saved
+    "#, annotations(read_only_hint = false, destructive_hint = false, idempotent_hint = true, open_world_hint = false))]
+    pub async fn switch_profile(&self, Parameters(params): Parameters<SwitchProfileParams>) -> Result<String, McpError> {
+        let state = self.state();
+        params.validate().map_err(invalid_params)?;
+
+        let lang = state.config.lang;
+        let previous = state.config.profile.clone();
+        let next = params.name.trim().to_string();
+
+        let payload = match Config::switch_profile(&next).and_then(|()| self.reload()) {
+            Ok(()) => serde_json::json!({
+                "status": msg(Msg::StatusSuccess, lang),
+                "previous_profile": previous,
+                "current_profile": next,
+                "available_profiles": Config::list_profiles(),
+                "message": msg_fmt(Msg::ProfileSwitched, lang, &[&previous, &next]),
+                "config_file": Config::config_file_path().to_string_lossy(),
+            }),
+            Err(e) => serde_json::json!({
+                "status": msg(Msg::StatusFailed, lang),
+                "available_profiles": Config::list_profiles(),
+                "message": msg_fmt(Msg::ProfileSwitchFailed, lang, &[&e]),
+            }),
+        };
+
+        serde_json::to_string_pretty(&payload).map_err(internal_error)
+    }
+
+    #[tool(description = r#"
+    Clears the in-memory search result and fetched page caches without restarting the server.
+
+    Safe to call when caching is disabled (`GROK_CACHE_TTL_SECS=0`, `GROK_FETCH_CACHE_TTL=0`); it then reports 0 entries.
+
+    Returns
+    -------
+    str
+        A JSON-encoded string containing:
+        - `evicted`: Number of cache entries removed
+        - `cache_enabled`: Whether search caching is enabled
+        - `fetch_cache_enabled`: Whether fetch caching is enabled
+    "#, annotations(read_only_hint = false, destructive_hint = true, idempotent_hint = true, open_world_hint = false))]
+    pub async fn clear_cache(&self, _params: Parameters<ClearCacheParams>) -> Result<String, McpError> {
+        let state = self.state();
+        let payload = serde_json::json!({
+            "evicted": state.client.clear_cache(),
+            "cache_enabled": state.config.cache_ttl_secs > 0,
+            "fetch_cache_enabled": state.config.fetch_cache_ttl_secs > 0,
+        });
+
+        serde_json::to_string_pretty(&payload).map_err(internal_error)
+    }
+
+    #[tool(description = r#"
+    Returns request metrics in the Prometheus text exposition format, for scraping or inspection.
+
+    Returns
+    -------
+    str
+        Prometheus metrics:
+        - `grok_requests_total`: API request attempts, including retries
+        - `grok_retries_total`: Attempts that were retried
+        - `grok_hedged_requests_total`: Second requests sent because the first had not started responding
+        - `grok_failures_total{error}`: Failed attempts by error kind (e.g. "timeout", "api", "rate_limited")
+        - `grok_stream_bytes_total`: Bytes received from streaming responses
+        - `grok_request_duration_seconds`: Histogram of attempt durations
+    "#, annotations(read_only_hint = true, idempotent_hint = true, open_world_hint = false))]
+    pub async fn metrics(&self, _params: Parameters<MetricsParams>) -> Result<String, McpError> {
+        let state = self.state();
+        Ok(state.client.render_metrics())
+    }
+
+    #[tool(description = r#"
+    Restores the persisted configuration to its defaults without deleting the config file.
+
+    Clears the saved model, the built-in tools setting and the active profile. Profile
+    definitions and unrecognized fields are kept unless `full` is true.
+
+    Parameters
+    ----------
+    full : bool, optional
+        Also remove profile definitions and unrecognized fields (default false)
+
+    Returns
+    -------
+    str
+        A JSON-encoded string containing:
+        - `status`: Success or error status
+        - `reset`: Names of the fields that were cleared
+        - `full`: Whether a full reset was performed
+        - `message`: Status message
+        - `config_file`: Path of the rewritten config file
+    "#, annotations(read_only_hint = false, destructive_hint = true, idempotent_hint = true, open_world_hint = false))]
+    pub async fn reset_config(&self, Parameters(params): Parameters<ResetConfigParams>) -> Result<String, McpError> {
+        let state = self.state();
+        let lang = state.config.lang;
+        let payload = match Config::reset(params.full) {
+            Ok(reset) => serde_json::json!({
+                "status": msg(Msg::StatusSuccess, lang),
+                "reset": reset,
+                "full": params.full,
+                "message": msg(Msg::ConfigReset, lang),
+                "config_file": Config::config_file_path().to_string_lossy(),
+            }),
+            Err(e) => serde_json::json!({
+                "status": msg(Msg::StatusFailed, lang),
+                "full": params.full,
+                "message": msg_fmt(Msg::ConfigResetFailed, lang, &[&e]),
+            }),
+        };
+
+        serde_json::to_string_pretty(&payload).map_err(internal_error)
     }
 
     #[tool(description = r#"
@@ -160,27 +823,29 @@ impl GrokSearchServer {
 
     Parameters: action - "on" (block built-in), "off" (allow built-in), "status" (check)
     Returns: JSON with current status and deny list
-    "#)]
+    "#, annotations(read_only_hint = false, destructive_hint = false, idempotent_hint = true, open_world_hint = false))]
     pub async fn toggle_builtin_tools(&self, Parameters(params): Parameters<ToggleBuiltinToolsParams>) -> Result<String, McpError> {
-        params.validate().map_err(|msg| McpError::invalid_params(msg, None))?;
+        let state = self.state();
+        params.validate().map_err(invalid_params)?;
 
         let action = params.action.trim().to_lowercase();
         let tools = ["WebFetch", "WebSearch"];
-        let mut blocked = self.config.builtin_tools_disabled;
+        let mut blocked = state.config.builtin_tools_disabled;
 
-        let message = match action.as_str() {
+        let key = match action.as_str() {
             "on" => {
                 blocked = true;
                 Config::save_builtin_tools_disabled(true).ok();
-                "官方工具已禁用"
+                Msg::BuiltinDisabled
             }
             "off" => {
                 blocked = false;
                 Config::save_builtin_tools_disabled(false).ok();
-                "官方工具已启用"
+                Msg::BuiltinEnabled
             }
-            _ => if blocked { "官方工具当前已禁用" } else { "官方工具当前已启用" }
+            _ => if blocked { Msg::BuiltinCurrentlyDisabled } else { Msg::BuiltinCurrentlyEnabled }
         };
+        let message = msg(key, state.config.lang);
 
         let payload = serde_json::json!({
             "blocked": blocked,
@@ -189,11 +854,131 @@ impl GrokSearchServer {
             "message": message,
         });
 
-        serde_json::to_string_pretty(&payload).map_err(|e| McpError::internal_error(e.to_string(), None))
+        serde_json::to_string_pretty(&payload).map_err(internal_error)
     }
 }
 
-#[tool_handler(router = Self::tool_router())]
+/// One-line summary of the settings a reload can change, with the API key masked.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn describe(config: &Config) -> String {
+    format!(
+        "api_url={} api_key={} model={} profile={}",
+        config.api_url, config.mask_api_key(), config.model, config.profile
+    )
+}
+
+/// Forwards client progress updates as MCP progress notifications when the caller
+/// supplied a progress token. The forwarding task ends once the sender is dropped.
+fn progress_sender(ctx: &RequestContext<RoleServer>) -> Option<ProgressSender> {
+    let token = ctx.meta.get_progress_token()?;
+    let peer = ctx.peer.clone();
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Progress>();
+
+    tokio::spawn(async move {
+        let mut last = f64::NEG_INFINITY;
+        while let Some(update) = rx.recv().await {
+            // Progress must increase; a retried stream restarts from zero, so skip until it catches up.
+            if update.progress <= last { continue; }
+            last = update.progress;
+            let param = ProgressNotificationParam {
+                progress_token: token.clone(),
+                progress: update.progress,
+                total: update.total,
+                message: Some(update.message),
+            };
+            if let Err(e) = peer.notify_progress(param).await {
+                tracing::debug!("Failed to send progress notification: {}", e);
+                break;
+            }
+        }
+    });
+
+    Some(tx)
+}
+
+/// Prefixes the message with the error class (e.g. `[RATE_LIMIT]`) and carries it as
+/// `{"code": ...}` in the error data, so agents can branch without parsing messages.
+fn tool_error(e: GrokError) -> McpError {
+    if let GrokError::InvalidParams(msg) = e {
+        return invalid_params(msg);
+    }
+    let mut data = error_data(e.code());
+    // Retried requests carry every attempt, so callers can tell repeated timeouts from mixed failures.
+    if let GrokError::MaxRetries { attempts_detail, elapsed, .. } | GrokError::AfterRetries { attempts_detail, elapsed, .. } = &e {
+        data["elapsed_ms"] = serde_json::json!(elapsed.as_millis() as u64);
+        data["attempts"] = attempts_detail.iter()
+            .map(|(attempt, error)| serde_json::json!({ "attempt": attempt, "error": error }))
+            .collect();
+    }
+    McpError::internal_error(format!("[{}] {}", e.code(), e), Some(data))
+}
+
+fn invalid_params(msg: String) -> McpError {
+    McpError::invalid_params(msg, Some(error_data("INVALID_PARAMS")))
+}
+
+/// Primes the client's connection pool (DNS, TCP and TLS) in the background so the first tool
+/// call does not pay the handshake. Clones share the pool, so the warm connection is reused.
+fn warm_up(client: GrokClient) {
+    tokio::spawn(async move {
+        let result = client.test_connection().await;
+        match result.status.as_str() {
+            "success" => tracing::info!("Warmup connection ready in {} ms", result.response_time_ms.unwrap_or_default()),
+            _ => tracing::warn!(
+                "Warmup connection failed: [{}] {}",
+                result.error_code.as_deref().unwrap_or("UNKNOWN"),
+                result.message.as_deref().unwrap_or_default()
+            ),
+        }
+    });
+}
+
+/// Rejects a caller-chosen model outside `GROK_ALLOWED_MODELS`.
+fn check_model_allowed(config: &Config, model: Option<&str>) -> Result<(), McpError> {
+    match model {
+        Some(model) if !config.is_model_allowed(model) => Err(McpError::invalid_params(
+            msg_fmt(Msg::ModelNotAllowed, config.lang, &[&model, &config.allowed_models.join(", ")]),
+            Some(serde_json::json!({ "code": "MODEL_NOT_ALLOWED", "allowed_models": config.allowed_models })),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn internal_error(e: impl std::fmt::Display) -> McpError {
+    McpError::internal_error(e.to_string(), Some(error_data("INTERNAL_ERROR")))
+}
+
+fn error_data(code: &str) -> serde_json::Value {
+    serde_json::json!({ "code": code })
+}
+
+/// Returns the model output as the first content block, followed by a JSON block
+/// with the responding model, token usage and, when the model is priced, the estimated cost.
+fn response_result(resp: GrokResponse, config: &Config) -> Result<CallToolResult, McpError> {
+    let mut metadata = serde_json::json!({
+        "model": resp.model,
+        "usage": resp.usage,
+    });
+    if let Some(cost) = estimated_cost(&resp, config) {
+        metadata["estimated_cost_usd"] = serde_json::json!(cost);
+    }
+    if let Some(token) = &resp.continue_token {
+        metadata["continue_token"] = serde_json::json!(token);
+    }
+    let metadata = serde_json::to_string_pretty(&metadata).map_err(internal_error)?;
+    Ok(CallToolResult::success(vec![Content::text(resp.content), Content::text(metadata)]))
+}
+
+/// Prices the reported usage with the `GROK_PRICING` rates of the responding model.
+/// `None` when the model has no rates or the backend reported no usage.
+fn estimated_cost(resp: &GrokResponse, config: &Config) -> Option<f64> {
+    let rates = config.pricing.get(&resp.model)?;
+    let usage = resp.usage.as_ref()?;
+    let cost = rates.cost(usage.prompt_tokens, usage.completion_tokens);
+    tracing::info!(model = %resp.model, cost_usd = cost, "Estimated request cost");
+    Some(cost)
+}
+
 impl ServerHandler for GrokSearchServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
@@ -204,8 +989,173 @@ impl ServerHandler for GrokSearchServer {
                 icons: None,
                 website_url: None,
             },
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            capabilities: ServerCapabilities::builder().enable_tools().enable_resources().build(),
             ..Default::default()
         }
     }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult { tools: self.tool_routes().list_all(), next_cursor: None, meta: None })
+    }
+
+    /// Runs a tool inside a span carrying a fresh request ID, so every log line of the call can be
+    /// correlated, and echoes the ID in the result or error.
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let request_id = format!("{:08x}", rand::random::<u32>());
+        let span = tracing::info_span!("tool", request_id = %request_id, name = %request.name);
+        let tcc = ToolCallContext::new(self, request, context);
+        match self.tool_routes().call(tcc).instrument(span).await {
+            Ok(result) => Ok(with_request_id(result, &request_id)),
+            Err(mut e) => {
+                match &mut e.data {
+                    Some(serde_json::Value::Object(data)) => {
+                        data.insert("request_id".into(), request_id.into());
+                    }
+                    data => *data = Some(serde_json::json!({ "request_id": request_id })),
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let state = self.state();
+        let mut config = RawResource::new(CONFIG_RESOURCE, "config");
+        config.description = Some("The persisted config file, with API keys masked".into());
+        config.mime_type = Some("application/json".into());
+        let mut resources = vec![config.no_annotation()];
+        if state.config.log_dir.is_some() {
+            let mut logs = RawResource::new(LOGS_RESOURCE, "logs");
+            logs.description = Some(format!("The last {LOG_TAIL_LINES} lines of the current log file"));
+            logs.mime_type = Some("text/plain".into());
+            resources.push(logs.no_annotation());
+        }
+        Ok(ListResourcesResult { resources, next_cursor: None, meta: None })
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let state = self.state();
+        let (text, mime_type) = match request.uri.as_str() {
+            CONFIG_RESOURCE => {
+                let file = Config::masked_file().map_err(tool_error)?;
+                (serde_json::to_string_pretty(&file).map_err(internal_error)?, "application/json")
+            }
+            LOGS_RESOURCE => {
+                let Some(dir) = &state.config.log_dir else {
+                    return Err(McpError::resource_not_found("Logging to a file is disabled (GROK_LOG_DIR is unset)", None));
+                };
+                (log_tail(Path::new(dir), LOG_TAIL_LINES).map_err(internal_error)?, "text/plain")
+            }
+            uri => return Err(McpError::resource_not_found(format!("Unknown resource: {uri}"), None)),
+        };
+        let contents = ResourceContents::TextResourceContents {
+            uri: request.uri,
+            mime_type: Some(mime_type.into()),
+            text,
+            meta: None,
+        };
+        Ok(ReadResourceResult { contents: vec![contents] })
+    }
+}
+
+/// Adds the request ID to the result's `_meta` and, when the last content block is a JSON
+/// object (a tool's status payload or the search/fetch metadata block), to that object too.
+/// The payload keeps its formatting: single-line JSON (e.g. `format: "compact"`) stays single-line.
+fn with_request_id(mut result: CallToolResult, request_id: &str) -> CallToolResult {
+    if let Some(RawContent::Text(block)) = result.content.last_mut().map(|c| &mut c.raw)
+        && let Ok(serde_json::Value::Object(mut payload)) = serde_json::from_str(&block.text)
+    {
+        payload.insert("request_id".into(), request_id.into());
+        let text = if block.text.trim().contains('\n') {
+            serde_json::to_string_pretty(&payload)
+        } else {
+            serde_json::to_string(&payload)
+        };
+        if let Ok(text) = text {
+            block.text = text;
+        }
+    }
+    result.meta.get_or_insert_with(Meta::new).insert("request_id".into(), request_id.into());
+    result
+}
+
+const CONFIG_RESOURCE: &str = "grok://config";
+const LOGS_RESOURCE: &str = "grok://logs";
+const LOG_TAIL_LINES: usize = 200;
+/// Only the end of the log file is read, so a large log stays cheap to tail.
+const LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+/// The last `lines` lines of the newest `grok-search.log.*` file in `dir`.
+fn log_tail(dir: &Path, lines: usize) -> std::io::Result<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut newest: Option<(std::time::SystemTime, PathBuf)> = None;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_name().to_string_lossy().starts_with("grok-search.log") {
+            continue;
+        }
+        let modified = entry.metadata()?.modified()?;
+        if newest.as_ref().is_none_or(|(time, _)| modified > *time) {
+            newest = Some((modified, entry.path()));
+        }
+    }
+    let Some((_, path)) = newest else { return Ok(String::new()) };
+
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(LOG_TAIL_BYTES)))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    let text = String::from_utf8_lossy(&bytes);
+    let all: Vec<&str> = text.lines().collect();
+    Ok(all[all.len().saturating_sub(lines)..].join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_of(result: &CallToolResult) -> &str {
+        match &result.content.last().unwrap().raw {
+            RawContent::Text(block) => &block.text,
+            _ => panic!("expected a text block"),
+        }
+    }
+
+    #[test]
+    fn request_id_keeps_compact_output_on_one_line() {
+        let result = CallToolResult::success(vec![Content::text(r#"{"config_complete":true}"#)]);
+        let result = with_request_id(result, "req-1");
+        let text = text_of(&result);
+        assert!(!text.contains('\n'));
+        let payload: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(payload["request_id"], "req-1");
+        assert_eq!(payload["config_complete"], true);
+    }
+
+    #[test]
+    fn request_id_keeps_pretty_output_pretty() {
+        let pretty = serde_json::to_string_pretty(&serde_json::json!({ "config_complete": true })).unwrap();
+        let result = with_request_id(CallToolResult::success(vec![Content::text(pretty)]), "req-2");
+        let text = text_of(&result);
+        assert!(text.contains('\n'));
+        assert!(text.contains("\"request_id\": \"req-2\""));
+    }
 }
@@ -2,4 +2,22 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
-pub struct GetConfigInfoParams {}
+pub struct GetConfigInfoParams {
+    /// "pretty" (default, indented) or "compact" (single-line JSON)
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+impl GetConfigInfoParams {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.format.as_deref().map(|f| f.trim().to_lowercase()) {
+            None => Ok(()),
+            Some(f) if f == "pretty" || f == "compact" => Ok(()),
+            Some(_) => Err("format must be 'pretty' or 'compact'".into()),
+        }
+    }
+
+    pub fn is_compact(&self) -> bool {
+        self.format.as_deref().is_some_and(|f| f.trim().eq_ignore_ascii_case("compact"))
+    }
+}
@@ -0,0 +1,48 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+const MAX_LINES: usize = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DebugStreamParams {
+    /// "search" (default) or "fetch"
+    #[serde(default = "default_kind")]
+    pub kind: String,
+
+    /// Search query, or the URL to fetch
+    pub input: String,
+
+    /// Stop after this many raw lines (1-5000, default 500)
+    #[serde(default = "default_max_lines")]
+    pub max_lines: usize,
+}
+
+fn default_kind() -> String { "search".into() }
+fn default_max_lines() -> usize { 500 }
+
+impl DebugStreamParams {
+    pub fn validate(&self) -> Result<(), String> {
+        let kind = self.kind.trim().to_lowercase();
+        if !matches!(kind.as_str(), "search" | "fetch") {
+            return Err("kind must be 'search' or 'fetch'".into());
+        }
+        let input = self.input.trim();
+        if input.is_empty() {
+            return Err("input cannot be empty".into());
+        }
+        if input.chars().count() > 2048 {
+            return Err("input exceeds 2048 characters".into());
+        }
+        if kind == "fetch" && !input.starts_with("http://") && !input.starts_with("https://") {
+            return Err("URL must use http or https scheme".into());
+        }
+        if self.max_lines < 1 || self.max_lines > MAX_LINES {
+            return Err(format!("max_lines must be between 1 and {MAX_LINES}"));
+        }
+        Ok(())
+    }
+
+    pub fn is_fetch(&self) -> bool {
+        self.kind.trim().eq_ignore_ascii_case("fetch")
+    }
+}
@@ -1,24 +1,211 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+const MAX_BATCH_URLS: usize = 20;
+const MIN_MAX_CHARS: usize = 200;
+/// Query parameters removed by `clean_url`, besides any `utm_*` parameter.
+const TRACKING_PARAMS: &[&str] = &["fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid"];
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct WebFetchParams {
-    /// URL to fetch (must be http or https)
+    /// URL to fetch (must be http or https; not needed with continue_token)
+    #[serde(default)]
     pub url: String,
+
+    /// Additional URLs to fetch in one batch (max 20 in total)
+    #[serde(default)]
+    pub urls: Vec<String>,
+
+    /// Output format: "markdown" (default), "text", or "html"
+    #[serde(default = "default_format")]
+    pub format: String,
+
+    /// "full" (default) returns the whole page; "summary" returns a short bulleted summary plus key links
+    #[serde(default = "default_mode")]
+    pub mode: String,
+
+    /// Approximate character budget for each page (at least 200); longer output is cut off
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+
+    /// Model for this call, overriding GROK_FETCH_MODEL and the configured model
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Overall request timeout in seconds for each page (5-300, default 120)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+
+    /// Refuse pages that the site's robots.txt disallows for this server (default false)
+    #[serde(default)]
+    pub respect_robots: bool,
+
+    /// CSS selector of the page region to extract, e.g. "article" or "table.pricing"; only that fragment is returned
+    #[serde(default)]
+    pub css_selector: Option<String>,
+
+    /// Strip tracking query parameters (utm_*, fbclid, gclid, ...) before fetching (default false)
+    #[serde(default)]
+    pub clean_url: bool,
+
+    /// Token from a truncated result's metadata; returns the next chunk of that page, reusing its URL and options
+    #[serde(default)]
+    pub continue_token: Option<String>,
 }
 
+fn default_format() -> String { "markdown".into() }
+fn default_mode() -> String { "full".into() }
+
 impl WebFetchParams {
     pub fn validate(&self) -> Result<(), String> {
-        let url = self.url.trim();
-        if url.is_empty() {
+        let urls = self.all_urls();
+        if self.continue_token.is_some() {
+            if self.continue_token().is_none() {
+                return Err("continue_token cannot be empty".into());
+            }
+            if self.is_batch() {
+                return Err("continue_token cannot be combined with urls".into());
+            }
+        } else if urls.is_empty() {
             return Err("URL cannot be empty".into());
         }
-        if url.len() > 2048 {
-            return Err("URL exceeds 2048 characters".into());
+        if urls.len() > MAX_BATCH_URLS {
+            return Err(format!("Cannot fetch more than {MAX_BATCH_URLS} URLs at once"));
         }
-        if !url.starts_with("http://") && !url.starts_with("https://") {
-            return Err("URL must use http or https scheme".into());
+        for url in &urls {
+            validate_url(url)?;
+            if self.clean_url && reqwest::Url::parse(url).is_err() {
+                return Err(format!("'{url}' is not a valid URL after removing tracking parameters"));
+            }
+        }
+        if self.urls.iter().any(|u| u.trim().is_empty()) {
+            return Err("URL cannot be empty".into());
+        }
+        let format = self.format.trim().to_lowercase();
+        if !matches!(format.as_str(), "markdown" | "text" | "html") {
+            return Err("format must be 'markdown', 'text', or 'html'".into());
+        }
+        let mode = self.mode.trim().to_lowercase();
+        if !matches!(mode.as_str(), "full" | "summary") {
+            return Err("mode must be 'full' or 'summary'".into());
+        }
+        if mode == "summary" && format != "markdown" {
+            return Err("mode 'summary' always returns Markdown and cannot be combined with another format".into());
+        }
+        if let Some(max_chars) = self.max_chars
+            && max_chars < MIN_MAX_CHARS
+        {
+            return Err(format!("max_chars must be at least {MIN_MAX_CHARS}"));
+        }
+        if let Some(selector) = &self.css_selector
+            && (selector.trim().is_empty() || selector.trim().chars().count() > 500)
+        {
+            return Err("css_selector must be 1 to 500 characters".into());
+        }
+        if let Some(model) = &self.model
+            && (model.trim().is_empty() || model.trim().chars().count() > 100)
+        {
+            return Err("model must be 1 to 100 characters".into());
+        }
+        if let Some(secs) = self.timeout_secs
+            && !(5..=300).contains(&secs)
+        {
+            return Err("timeout_secs must be between 5 and 300".into());
         }
         Ok(())
     }
+
+    pub fn css_selector(&self) -> Option<String> {
+        self.css_selector.as_deref().map(str::trim).map(String::from)
+    }
+
+    /// The per-call model override, trimmed.
+    pub fn model(&self) -> Option<String> {
+        self.model.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from)
+    }
+
+    pub fn continue_token(&self) -> Option<String> {
+        self.continue_token.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from)
+    }
+
+    /// True when the caller used the `urls` batch form.
+    pub fn is_batch(&self) -> bool {
+        !self.urls.is_empty()
+    }
+
+    /// `url` followed by `urls`, trimmed, with blanks and duplicates removed. With `clean_url`,
+    /// tracking parameters are stripped first, so URLs differing only in them are fetched once.
+    pub fn all_urls(&self) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for url in std::iter::once(&self.url).chain(&self.urls) {
+            let url = url.trim();
+            let url = if self.clean_url { strip_tracking_params(url) } else { url.to_string() };
+            if !url.is_empty() && !out.contains(&url) {
+                out.push(url);
+            }
+        }
+        out
+    }
+}
+
+/// Removes tracking query parameters, keeping the rest in order. Unparseable URLs are
+/// returned unchanged for `validate` to reject.
+fn strip_tracking_params(url: &str) -> String {
+    let Ok(mut parsed) = reqwest::Url::parse(url) else { return url.to_string() };
+    if parsed.query().is_none() {
+        return url.to_string();
+    }
+    let kept: Vec<(String, String)> = parsed.query_pairs()
+        .into_owned()
+        .filter(|(key, _)| {
+            let key = key.to_lowercase();
+            !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_str())
+        })
+        .collect();
+    if kept.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    parsed.to_string()
+}
+
+fn validate_url(url: &str) -> Result<(), String> {
+    if url.chars().count() > 2048 {
+        return Err("URL exceeds 2048 characters".into());
+    }
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err("URL must use http or https scheme".into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(value: serde_json::Value) -> WebFetchParams {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn cjk_url(chars: usize) -> String {
+        let base = "https://example.com/";
+        format!("{base}{}", "页".repeat(chars - base.chars().count()))
+    }
+
+    #[test]
+    fn url_limit_counts_characters_not_bytes() {
+        assert!(params(serde_json::json!({ "url": cjk_url(700) })).validate().is_ok());
+        assert!(params(serde_json::json!({ "url": cjk_url(2048) })).validate().is_ok());
+        let err = params(serde_json::json!({ "url": cjk_url(2049) })).validate().unwrap_err();
+        assert_eq!(err, "URL exceeds 2048 characters");
+    }
+
+    #[test]
+    fn model_limit_counts_characters_not_bytes() {
+        let url = "https://example.com/";
+        assert!(params(serde_json::json!({ "url": url, "model": "模".repeat(100) })).validate().is_ok());
+        let err = params(serde_json::json!({ "url": url, "model": "模".repeat(101) })).validate().unwrap_err();
+        assert_eq!(err, "model must be 1 to 100 characters");
+    }
 }
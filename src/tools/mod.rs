@@ -3,9 +3,21 @@ pub mod fetch;
 pub mod config;
 pub mod model;
 pub mod toggle;
+pub mod profile;
+pub mod health;
+pub mod cache;
+pub mod reset;
+pub mod metrics;
+pub mod debug;
 
 pub use search::WebSearchParams;
 pub use fetch::WebFetchParams;
 pub use config::GetConfigInfoParams;
-pub use model::SwitchModelParams;
+pub use model::{ListModelsParams, SwitchModelParams, TestModelParams};
 pub use toggle::ToggleBuiltinToolsParams;
+pub use profile::SwitchProfileParams;
+pub use health::HealthCheckParams;
+pub use cache::ClearCacheParams;
+pub use reset::ResetConfigParams;
+pub use metrics::MetricsParams;
+pub use debug::DebugStreamParams;
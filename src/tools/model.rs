@@ -1,10 +1,17 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ListModelsParams {}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SwitchModelParams {
     /// Model name to switch to (e.g., "grok-4-fast", "grok-2-latest")
     pub model: String,
+
+    /// Verify the model exists via the /models endpoint before saving (default true)
+    #[serde(default)]
+    pub validate: Option<bool>,
 }
 
 impl SwitchModelParams {
@@ -13,9 +20,60 @@ impl SwitchModelParams {
         if model.is_empty() {
             return Err("Model name cannot be empty".into());
         }
-        if model.len() > 100 {
+        if model.chars().count() > 100 {
+            return Err("Model name exceeds 100 characters".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TestModelParams {
+    /// Model to probe (not persisted)
+    pub model: String,
+
+    /// Search query to run against the model (max 2000 characters)
+    pub query: String,
+}
+
+impl TestModelParams {
+    pub fn validate(&self) -> Result<(), String> {
+        let model = self.model.trim();
+        if model.is_empty() {
+            return Err("Model name cannot be empty".into());
+        }
+        if model.chars().count() > 100 {
             return Err("Model name exceeds 100 characters".into());
         }
+        let query = self.query.trim();
+        if query.is_empty() {
+            return Err("Query cannot be empty".into());
+        }
+        if query.chars().count() > 2000 {
+            return Err("Query exceeds 2000 characters".into());
+        }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switch_model_limit_counts_characters_not_bytes() {
+        let params = |model: String| SwitchModelParams { model, validate: None };
+        assert!(params("模".repeat(40)).validate().is_ok());
+        assert!(params("模".repeat(100)).validate().is_ok());
+        assert_eq!(params("模".repeat(101)).validate().unwrap_err(), "Model name exceeds 100 characters");
+    }
+
+    #[test]
+    fn test_model_limits_count_characters_not_bytes() {
+        let params = |model: String, query: String| TestModelParams { model, query };
+        assert!(params("模".repeat(100), "中".repeat(700)).validate().is_ok());
+        assert!(params("模".repeat(100), "中".repeat(2000)).validate().is_ok());
+        assert_eq!(params("模".repeat(101), "q".into()).validate().unwrap_err(), "Model name exceeds 100 characters");
+        assert_eq!(params("m".into(), "中".repeat(2001)).validate().unwrap_err(), "Query exceeds 2000 characters");
+    }
+}
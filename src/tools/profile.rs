@@ -0,0 +1,34 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SwitchProfileParams {
+    /// Profile name to activate ("default" restores the top-level settings)
+    pub name: String,
+}
+
+impl SwitchProfileParams {
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("Profile name cannot be empty".into());
+        }
+        if name.chars().count() > 100 {
+            return Err("Profile name exceeds 100 characters".into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn name_limit_counts_characters_not_bytes() {
+        let params = |name: String| SwitchProfileParams { name };
+        assert!(params("档".repeat(40)).validate().is_ok());
+        assert!(params("档".repeat(100)).validate().is_ok());
+        assert_eq!(params("档".repeat(101)).validate().unwrap_err(), "Profile name exceeds 100 characters");
+    }
+}
@@ -0,0 +1,9 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ResetConfigParams {
+    /// Also remove profile definitions and any unrecognized fields (default false)
+    #[serde(default)]
+    pub full: bool,
+}
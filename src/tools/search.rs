@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -6,21 +7,157 @@ pub struct WebSearchParams {
     /// Search query (max 2000 characters)
     pub query: String,
 
-    /// Platform hint (e.g., "twitter", "github", "reddit")
+    /// Platforms to focus on, comma-separated (e.g., "twitter", "github,reddit"; max 5); use "raw:<name>" for one not in the known list
     #[serde(default)]
     pub platform: String,
 
-    /// Minimum number of results (1-50, default 3)
-    #[serde(default = "default_min_results")]
-    pub min_results: u32,
+    /// Minimum number of results (1-50, default GROK_DEFAULT_MIN_RESULTS or 3)
+    #[serde(default)]
+    pub min_results: Option<u32>,
+
+    /// Maximum number of results (1-100, default GROK_DEFAULT_MAX_RESULTS or 10)
+    #[serde(default)]
+    pub max_results: Option<u32>,
+
+    /// Only include results published on or after this date (ISO 8601, e.g. "2024-01-31")
+    #[serde(default)]
+    pub start_date: Option<String>,
+
+    /// Only include results published on or before this date (ISO 8601, e.g. "2024-02-29")
+    #[serde(default)]
+    pub end_date: Option<String>,
+
+    /// Domains to exclude from results (bare hostnames like "pinterest.com", max 20)
+    #[serde(default)]
+    pub exclude_domains: Vec<String>,
+
+    /// Only return results from these domains (bare hostnames, max 20); takes precedence over exclude_domains
+    #[serde(default)]
+    pub include_domains: Vec<String>,
+
+    /// Restrict results to a language, as an ISO 639-1 code (e.g. "en", "zh", "ja")
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Result ordering: "relevance" (default) or "recency"
+    #[serde(default = "default_sort")]
+    pub sort: String,
 
-    /// Maximum number of results (1-100, default 10)
-    #[serde(default = "default_max_results")]
-    pub max_results: u32,
+    /// Content filter level: "off", "moderate" (default), or "strict"
+    #[serde(default = "default_safe_search")]
+    pub safe_search: String,
+
+    /// Require a JSON array of {title, url, snippet, published_date, source}, asking the model once to correct invalid output (default false)
+    #[serde(default)]
+    pub strict_json: bool,
+
+    /// Also return a top-level `sources` array of {title, url}; the output becomes an object {results, sources} (default false)
+    #[serde(default)]
+    pub include_sources: bool,
+
+    /// Remove results with duplicate URLs from JSON array output (default true)
+    #[serde(default = "default_dedupe")]
+    pub dedupe: bool,
+
+    /// Model for this call, overriding GROK_SEARCH_MODEL and the configured model
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Overall request timeout in seconds for this call (5-300, default 120)
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+
+    /// Forward partial output as progress notifications while the search runs (default false)
+    #[serde(default)]
+    pub stream: bool,
+
+    /// Cut queries over 2000 characters at a word boundary instead of rejecting them (default false)
+    #[serde(default)]
+    pub truncate_query: bool,
+
+    /// Earlier conversation turns for follow-up queries, oldest first (max 20 messages, 20000 characters in total)
+    #[serde(default)]
+    pub context: Vec<ContextMessage>,
 }
 
-fn default_min_results() -> u32 { 3 }
-fn default_max_results() -> u32 { 10 }
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ContextMessage {
+    /// "user" or "assistant"
+    pub role: String,
+
+    /// Message text
+    pub content: String,
+}
+
+pub const DEFAULT_MIN_RESULTS: u32 = 3;
+pub const DEFAULT_MAX_RESULTS: u32 = 10;
+const MAX_QUERY_CHARS: usize = 2000;
+const RAW_PLATFORM_PREFIX: &str = "raw:";
+const MAX_PLATFORMS: usize = 5;
+
+/// Known platforms by normalized key (lowercase, no spaces, dots, dashes or underscores)
+/// and the name used in the prompt.
+const KNOWN_PLATFORMS: &[(&str, &str)] = &[
+    ("twitter", "Twitter/X"), ("x", "Twitter/X"), ("github", "GitHub"), ("gitlab", "GitLab"),
+    ("reddit", "Reddit"), ("hackernews", "Hacker News"), ("hn", "Hacker News"),
+    ("stackoverflow", "Stack Overflow"), ("youtube", "YouTube"), ("linkedin", "LinkedIn"),
+    ("medium", "Medium"), ("devto", "DEV Community"), ("discord", "Discord"), ("arxiv", "arXiv"),
+    ("wikipedia", "Wikipedia"), ("producthunt", "Product Hunt"), ("npm", "npm"), ("pypi", "PyPI"),
+    ("cratesio", "crates.io"), ("zhihu", "Zhihu"), ("weibo", "Weibo"), ("bilibili", "Bilibili"),
+    ("v2ex", "V2EX"), ("juejin", "Juejin"), ("csdn", "CSDN"),
+];
+const MAX_DOMAIN_ENTRIES: usize = 20;
+const MAX_CONTEXT_MESSAGES: usize = 20;
+const MAX_CONTEXT_CHARS: usize = 20_000;
+
+/// ISO 639-1 language codes and their English names.
+const ISO_639_1: &[(&str, &str)] = &[
+    ("aa", "Afar"), ("ab", "Abkhazian"), ("ae", "Avestan"), ("af", "Afrikaans"), ("ak", "Akan"),
+    ("am", "Amharic"), ("an", "Aragonese"), ("ar", "Arabic"), ("as", "Assamese"), ("av", "Avaric"),
+    ("ay", "Aymara"), ("az", "Azerbaijani"), ("ba", "Bashkir"), ("be", "Belarusian"),
+    ("bg", "Bulgarian"), ("bi", "Bislama"), ("bm", "Bambara"), ("bn", "Bengali"),
+    ("bo", "Tibetan"), ("br", "Breton"), ("bs", "Bosnian"), ("ca", "Catalan"), ("ce", "Chechen"),
+    ("ch", "Chamorro"), ("co", "Corsican"), ("cr", "Cree"), ("cs", "Czech"),
+    ("cu", "Church Slavic"), ("cv", "Chuvash"), ("cy", "Welsh"), ("da", "Danish"),
+    ("de", "German"), ("dv", "Divehi"), ("dz", "Dzongkha"), ("ee", "Ewe"), ("el", "Greek"),
+    ("en", "English"), ("eo", "Esperanto"), ("es", "Spanish"), ("et", "Estonian"),
+    ("eu", "Basque"), ("fa", "Persian"), ("ff", "Fulah"), ("fi", "Finnish"), ("fj", "Fijian"),
+    ("fo", "Faroese"), ("fr", "French"), ("fy", "Western Frisian"), ("ga", "Irish"),
+    ("gd", "Scottish Gaelic"), ("gl", "Galician"), ("gn", "Guarani"), ("gu", "Gujarati"),
+    ("gv", "Manx"), ("ha", "Hausa"), ("he", "Hebrew"), ("hi", "Hindi"), ("ho", "Hiri Motu"),
+    ("hr", "Croatian"), ("ht", "Haitian"), ("hu", "Hungarian"), ("hy", "Armenian"),
+    ("hz", "Herero"), ("ia", "Interlingua"), ("id", "Indonesian"), ("ie", "Interlingue"),
+    ("ig", "Igbo"), ("ii", "Sichuan Yi"), ("ik", "Inupiaq"), ("io", "Ido"), ("is", "Icelandic"),
+    ("it", "Italian"), ("iu", "Inuktitut"), ("ja", "Japanese"), ("jv", "Javanese"),
+    ("ka", "Georgian"), ("kg", "Kongo"), ("ki", "Kikuyu"), ("kj", "Kuanyama"), ("kk", "Kazakh"),
+    ("kl", "Kalaallisut"), ("km", "Khmer"), ("kn", "Kannada"), ("ko", "Korean"), ("kr", "Kanuri"),
+    ("ks", "Kashmiri"), ("ku", "Kurdish"), ("kv", "Komi"), ("kw", "Cornish"), ("ky", "Kyrgyz"),
+    ("la", "Latin"), ("lb", "Luxembourgish"), ("lg", "Ganda"), ("li", "Limburgish"),
+    ("ln", "Lingala"), ("lo", "Lao"), ("lt", "Lithuanian"), ("lu", "Luba-Katanga"),
+    ("lv", "Latvian"), ("mg", "Malagasy"), ("mh", "Marshallese"), ("mi", "Maori"),
+    ("mk", "Macedonian"), ("ml", "Malayalam"), ("mn", "Mongolian"), ("mr", "Marathi"),
+    ("ms", "Malay"), ("mt", "Maltese"), ("my", "Burmese"), ("na", "Nauru"),
+    ("nb", "Norwegian Bokmal"), ("nd", "North Ndebele"), ("ne", "Nepali"), ("ng", "Ndonga"),
+    ("nl", "Dutch"), ("nn", "Norwegian Nynorsk"), ("no", "Norwegian"), ("nr", "South Ndebele"),
+    ("nv", "Navajo"), ("ny", "Chichewa"), ("oc", "Occitan"), ("oj", "Ojibwa"), ("om", "Oromo"),
+    ("or", "Oriya"), ("os", "Ossetian"), ("pa", "Punjabi"), ("pi", "Pali"), ("pl", "Polish"),
+    ("ps", "Pashto"), ("pt", "Portuguese"), ("qu", "Quechua"), ("rm", "Romansh"), ("rn", "Rundi"),
+    ("ro", "Romanian"), ("ru", "Russian"), ("rw", "Kinyarwanda"), ("sa", "Sanskrit"),
+    ("sc", "Sardinian"), ("sd", "Sindhi"), ("se", "Northern Sami"), ("sg", "Sango"),
+    ("si", "Sinhala"), ("sk", "Slovak"), ("sl", "Slovenian"), ("sm", "Samoan"), ("sn", "Shona"),
+    ("so", "Somali"), ("sq", "Albanian"), ("sr", "Serbian"), ("ss", "Swati"),
+    ("st", "Southern Sotho"), ("su", "Sundanese"), ("sv", "Swedish"), ("sw", "Swahili"),
+    ("ta", "Tamil"), ("te", "Telugu"), ("tg", "Tajik"), ("th", "Thai"), ("ti", "Tigrinya"),
+    ("tk", "Turkmen"), ("tl", "Tagalog"), ("tn", "Tswana"), ("to", "Tonga"), ("tr", "Turkish"),
+    ("ts", "Tsonga"), ("tt", "Tatar"), ("tw", "Twi"), ("ty", "Tahitian"), ("ug", "Uyghur"),
+    ("uk", "Ukrainian"), ("ur", "Urdu"), ("uz", "Uzbek"), ("ve", "Venda"), ("vi", "Vietnamese"),
+    ("vo", "Volapuk"), ("wa", "Walloon"), ("wo", "Wolof"), ("xh", "Xhosa"), ("yi", "Yiddish"),
+    ("yo", "Yoruba"), ("za", "Zhuang"), ("zh", "Chinese"), ("zu", "Zulu"),
+];
+
+fn default_sort() -> String { "relevance".into() }
+fn default_safe_search() -> String { "moderate".into() }
+fn default_dedupe() -> bool { true }
 
 impl WebSearchParams {
     pub fn validate(&self) -> Result<(), String> {
@@ -28,18 +165,288 @@ impl WebSearchParams {
         if query.is_empty() {
             return Err("Query cannot be empty".into());
         }
-        if query.len() > 2000 {
-            return Err("Query exceeds 2000 characters".into());
+        if !self.truncate_query && query.chars().count() > MAX_QUERY_CHARS {
+            return Err(format!("Query exceeds {MAX_QUERY_CHARS} characters (set truncate_query=true to cut it instead)"));
         }
-        if self.min_results < 1 || self.min_results > 50 {
+        if !(1..=50).contains(&self.min_results()) {
             return Err("min_results must be between 1 and 50".into());
         }
-        if self.max_results < 1 || self.max_results > 100 {
+        if !(1..=100).contains(&self.max_results()) {
             return Err("max_results must be between 1 and 100".into());
         }
-        if self.min_results > self.max_results {
+        if self.min_results() > self.max_results() {
             return Err("min_results cannot be greater than max_results".into());
         }
+
+        let platforms = self.platform_entries();
+        if platforms.len() > MAX_PLATFORMS {
+            return Err(format!("platform cannot list more than {MAX_PLATFORMS} platforms"));
+        }
+        for platform in platforms {
+            validate_platform(platform)?;
+        }
+
+        let start = parse_date("start_date", self.start_date.as_deref())?;
+        let end = parse_date("end_date", self.end_date.as_deref())?;
+        if let (Some(start), Some(end)) = (start, end)
+            && start > end
+        {
+            return Err("start_date cannot be later than end_date".into());
+        }
+
+        validate_domains("exclude_domains", &self.exclude_domains)?;
+        validate_domains("include_domains", &self.include_domains)?;
+        let excluded = self.exclude_domains();
+        if let Some(domain) = self.include_domains().into_iter().find(|d| excluded.contains(d)) {
+            return Err(format!("'{domain}' cannot appear in both include_domains and exclude_domains"));
+        }
+
+        if let Some(code) = self.language_code()
+            && language_name(&code).is_none()
+        {
+            return Err(format!("language '{code}' is not a known ISO 639-1 code"));
+        }
+
+        let sort = self.sort.trim().to_lowercase();
+        if !matches!(sort.as_str(), "relevance" | "recency") {
+            return Err("sort must be 'relevance' or 'recency'".into());
+        }
+        let safe_search = self.safe_search.trim().to_lowercase();
+        if !matches!(safe_search.as_str(), "off" | "moderate" | "strict") {
+            return Err("safe_search must be 'off', 'moderate', or 'strict'".into());
+        }
+        if let Some(model) = &self.model
+            && (model.trim().is_empty() || model.trim().chars().count() > 100)
+        {
+            return Err("model must be 1 to 100 characters".into());
+        }
+        if let Some(secs) = self.timeout_secs
+            && !(5..=300).contains(&secs)
+        {
+            return Err("timeout_secs must be between 5 and 300".into());
+        }
+        validate_context(&self.context)?;
         Ok(())
     }
+
+    /// Context turns as `(role, content)`, with roles lowercased and content trimmed.
+    pub fn context(&self) -> Vec<(String, String)> {
+        self.context.iter()
+            .map(|m| (m.role.trim().to_lowercase(), m.content.trim().to_string()))
+            .collect()
+    }
+
+    /// Fills an unset `min_results` / `max_results` with the configured defaults.
+    pub fn with_result_defaults(mut self, min: u32, max: u32) -> Self {
+        self.min_results.get_or_insert(min);
+        self.max_results.get_or_insert(max);
+        self
+    }
+
+    pub fn min_results(&self) -> u32 {
+        self.min_results.unwrap_or(DEFAULT_MIN_RESULTS)
+    }
+
+    pub fn max_results(&self) -> u32 {
+        self.max_results.unwrap_or(DEFAULT_MAX_RESULTS)
+    }
+
+    /// The trimmed query, cut to the length limit when `truncate_query` is set.
+    pub fn query(&self) -> String {
+        let query = self.query.trim();
+        if !self.truncate_query {
+            return query.to_string();
+        }
+        truncate_at_word(query, MAX_QUERY_CHARS).trim_end().to_string()
+    }
+
+    /// Platform names for the prompt, in order and without duplicates: the canonical name of
+    /// each known platform, or the text after `raw:` verbatim.
+    pub fn platforms(&self) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for platform in self.platform_entries() {
+            let name = match platform.strip_prefix(RAW_PLATFORM_PREFIX) {
+                Some(raw) => raw.trim().to_string(),
+                None => known_platform(platform).map(String::from).unwrap_or_default(),
+            };
+            if !name.is_empty() && !out.contains(&name) {
+                out.push(name);
+            }
+        }
+        out
+    }
+
+    fn platform_entries(&self) -> Vec<&str> {
+        self.platform.split(',').map(str::trim).filter(|s| !s.is_empty()).collect()
+    }
+
+    /// The per-call model override, trimmed.
+    pub fn model(&self) -> Option<String> {
+        self.model.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from)
+    }
+
+    /// Human-readable language constraint, e.g. "Japanese (ja)".
+    pub fn language(&self) -> Option<String> {
+        let code = self.language_code()?;
+        language_name(&code).map(|name| format!("{name} ({code})"))
+    }
+
+    fn language_code(&self) -> Option<String> {
+        self.language.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_lowercase)
+    }
+
+    pub fn exclude_domains(&self) -> Vec<String> {
+        normalize_domains(&self.exclude_domains)
+    }
+
+    pub fn include_domains(&self) -> Vec<String> {
+        normalize_domains(&self.include_domains)
+    }
+
+    pub fn start_date(&self) -> Option<NaiveDate> {
+        parse_date("start_date", self.start_date.as_deref()).ok().flatten()
+    }
+
+    pub fn end_date(&self) -> Option<NaiveDate> {
+        parse_date("end_date", self.end_date.as_deref()).ok().flatten()
+    }
+}
+
+fn known_platform(name: &str) -> Option<&'static str> {
+    let key: String = name.chars()
+        .filter(|c| !matches!(c, ' ' | '.' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect();
+    KNOWN_PLATFORMS.iter().find(|(k, _)| *k == key).map(|(_, display)| *display)
+}
+
+fn validate_platform(platform: &str) -> Result<(), String> {
+    if let Some(raw) = platform.strip_prefix(RAW_PLATFORM_PREFIX) {
+        return match raw.trim().chars().count() {
+            0 => Err(format!("platform '{platform}' needs a name after '{RAW_PLATFORM_PREFIX}'")),
+            1..=100 => Ok(()),
+            _ => Err("platform exceeds 100 characters".into()),
+        };
+    }
+    if known_platform(platform).is_some() {
+        return Ok(());
+    }
+    let known: Vec<&str> = KNOWN_PLATFORMS.iter().map(|(k, _)| *k).collect();
+    Err(format!(
+        "Unknown platform '{platform}'. Known platforms: {}. Prefix with '{RAW_PLATFORM_PREFIX}' to use any other platform (e.g. \"raw:Mastodon\")",
+        known.join(", ")
+    ))
+}
+
+fn validate_context(context: &[ContextMessage]) -> Result<(), String> {
+    if context.len() > MAX_CONTEXT_MESSAGES {
+        return Err(format!("context cannot contain more than {MAX_CONTEXT_MESSAGES} messages"));
+    }
+    let mut total = 0;
+    for message in context {
+        let role = message.role.trim().to_lowercase();
+        if !matches!(role.as_str(), "user" | "assistant") {
+            return Err(format!("context role '{}' must be 'user' or 'assistant'", message.role.trim()));
+        }
+        if message.content.trim().is_empty() {
+            return Err("context message content cannot be empty".into());
+        }
+        total += message.content.trim().chars().count();
+    }
+    if total > MAX_CONTEXT_CHARS {
+        return Err(format!("context exceeds {MAX_CONTEXT_CHARS} characters in total"));
+    }
+    Ok(())
+}
+
+/// Cuts `text` to at most `max` characters, backing up to the last whitespace when the cut
+/// would split a word. Text without whitespace (e.g. Chinese) is cut at the character limit.
+fn truncate_at_word(text: &str, max: usize) -> &str {
+    let Some((end, next)) = text.char_indices().nth(max) else { return text };
+    let head = &text[..end];
+    if next.is_whitespace() {
+        return head;
+    }
+    match head.rfind(char::is_whitespace) {
+        Some(space) => &head[..space],
+        None => head,
+    }
+}
+
+/// Accepts a plain ISO 8601 date ("2024-01-31") or a full RFC 3339 timestamp,
+/// in which case only the date part is kept. Blank values count as unset.
+fn parse_date(name: &str, raw: Option<&str>) -> Result<Option<NaiveDate>, String> {
+    let Some(raw) = raw.map(str::trim).filter(|s| !s.is_empty()) else { return Ok(None) };
+    NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(raw).map(|dt| dt.date_naive()))
+        .map(Some)
+        .map_err(|_| format!("{name} must be an ISO 8601 date (YYYY-MM-DD)"))
+}
+
+fn validate_domains(name: &str, domains: &[String]) -> Result<(), String> {
+    if domains.len() > MAX_DOMAIN_ENTRIES {
+        return Err(format!("{name} cannot contain more than {MAX_DOMAIN_ENTRIES} entries"));
+    }
+    for domain in domains {
+        if !is_hostname(domain.trim()) {
+            return Err(format!("{name} entry '{}' must be a bare hostname (e.g. \"example.com\")", domain.trim()));
+        }
+    }
+    Ok(())
+}
+
+fn normalize_domains(domains: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for domain in domains {
+        let domain = domain.trim().trim_end_matches('.').to_lowercase();
+        if !out.contains(&domain) {
+            out.push(domain);
+        }
+    }
+    out
+}
+
+/// A hostname has no scheme, path, port or whitespace, at least two labels,
+/// and each label is 1-63 alphanumeric/hyphen characters not starting or ending with '-'.
+fn is_hostname(host: &str) -> bool {
+    let host = host.trim_end_matches('.');
+    if host.is_empty() || host.len() > 253 || !host.contains('.') {
+        return false;
+    }
+    host.split('.').all(|label| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+fn language_name(code: &str) -> Option<&'static str> {
+    ISO_639_1.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(value: serde_json::Value) -> WebSearchParams {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn query_limit_counts_characters_not_bytes() {
+        // 700 CJK characters are 2100 bytes, over the limit only if bytes were counted.
+        assert!(params(serde_json::json!({ "query": "中".repeat(700) })).validate().is_ok());
+        assert!(params(serde_json::json!({ "query": "中".repeat(2000) })).validate().is_ok());
+        let err = params(serde_json::json!({ "query": "中".repeat(2001) })).validate().unwrap_err();
+        assert!(err.starts_with("Query exceeds 2000 characters"), "{err}");
+    }
+
+    #[test]
+    fn model_limit_counts_characters_not_bytes() {
+        assert!(params(serde_json::json!({ "query": "q", "model": "模".repeat(100) })).validate().is_ok());
+        let err = params(serde_json::json!({ "query": "q", "model": "模".repeat(101) })).validate().unwrap_err();
+        assert_eq!(err, "model must be 1 to 100 characters");
+    }
 }